@@ -0,0 +1,149 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::{self, Display};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+use crossterm::style::Stylize;
+#[cfg(feature = "web")]
+use serde::{Deserialize, Serialize};
+
+/// Whether [`CountingAllocator`] is the global allocator.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+/// The number of allocations (including reallocations) made so far.
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+/// The number of bytes currently allocated.
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+/// The most bytes allocated at once since the last [`MemoryProbe`] started.
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// A global allocator which counts allocations and tracks peak heap usage, so
+/// that `raoc` can report the memory used by each part of a solution.
+///
+/// Memory is only measured if this is installed as the global allocator in
+/// your solution:
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: raoc::CountingAllocator = raoc::CountingAllocator;
+/// ```
+///
+/// Allocations are delegated to the [`System`] allocator. Every thread's
+/// allocations are counted, including the spinner shown while a solution runs,
+/// so very small figures are approximate.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn record_allocation(size: usize) {
+        // only written once, so that allocating doesn't keep contending for it
+        if !INSTALLED.load(Ordering::Relaxed) {
+            INSTALLED.store(true, Ordering::Relaxed);
+        }
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(current, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: every method delegates to `System`, only updating counters on the
+// side.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: the caller upholds `alloc`'s contract
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            Self::record_allocation(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: the caller upholds `alloc_zeroed`'s contract
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            Self::record_allocation(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: the caller upholds `dealloc`'s contract
+        unsafe { System.dealloc(ptr, layout) };
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // SAFETY: the caller upholds `realloc`'s contract
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+            Self::record_allocation(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// The heap memory used while part of a solution ran.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct MemoryUsage {
+    /// The number of allocations (including reallocations) made.
+    pub allocations: u64,
+    /// The most memory allocated at once, in bytes, beyond what was already
+    /// allocated beforehand.
+    pub peak_bytes: usize,
+}
+
+impl Display for MemoryUsage {
+    #[allow(clippy::cast_precision_loss)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let peak = match self.peak_bytes {
+            bytes if bytes < 1 << 10 => format!("{bytes}B"),
+            bytes if bytes < 1 << 20 => format!("{:.2}KiB", bytes as f64 / 1024.0),
+            bytes => format!("{:.2}MiB", bytes as f64 / (1024.0 * 1024.0)),
+        };
+        write!(
+            f,
+            "{} {}{} {}",
+            self.allocations.to_string().blue(),
+            if self.allocations == 1 {
+                "allocation"
+            } else {
+                "allocations"
+            }
+            .yellow(),
+            ", peak".yellow(),
+            peak.blue(),
+        )
+    }
+}
+
+/// A measurement of the memory used from when it started.
+#[cfg(feature = "web")]
+pub(crate) struct MemoryProbe {
+    allocations: u64,
+    baseline: usize,
+}
+
+#[cfg(feature = "web")]
+impl MemoryProbe {
+    /// Start measuring memory, if [`CountingAllocator`] is installed.
+    ///
+    /// Only one probe can be measuring peak usage at a time.
+    pub(crate) fn start() -> Option<Self> {
+        if !INSTALLED.load(Ordering::Relaxed) {
+            return None;
+        }
+        let baseline = CURRENT.load(Ordering::Relaxed);
+        PEAK.store(baseline, Ordering::Relaxed);
+        Some(Self {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            baseline,
+        })
+    }
+
+    pub(crate) fn finish(self) -> MemoryUsage {
+        MemoryUsage {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed) - self.allocations,
+            peak_bytes: PEAK.load(Ordering::Relaxed).saturating_sub(self.baseline),
+        }
+    }
+}
@@ -1,35 +1,100 @@
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::future::Future;
+use std::hint::black_box;
+use std::path::Path;
 use std::pin::Pin;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 use chrono::{DateTime, Datelike, TimeZone, Utc};
 use crossterm::style::{style, Stylize};
+use reqwest::StatusCode;
 use tokio::fs;
 
 use super::internal_util::{
     calculate_practice_result,
+    catch_panic,
+    check_answer_accepted,
+    dequeue,
+    enqueue,
     get,
     load_token_from_stdin,
+    lock,
     make,
+    migrate,
     post,
     practice_result_for,
+    queued_answers,
+    record_run,
     wait,
-    work,
+    work_with_time_limit,
+    write_atomic,
+};
+use crate::cache::{
+    day_dir,
+    input_file,
+    input_metadata_file,
+    private_leaderboard_file,
+    read_submissions,
+    solution_file,
+    submissions_file,
+    verify_input,
+    year_dir,
+    InputMetadata,
+    CALENDAR,
+    GLOBAL_STATS,
+    PERSONAL_STATS,
+    PRIVATE_LEADERBOARDS,
+    SUBMISSIONS,
+};
+use crate::data::{
+    base_url,
+    calendar_url,
+    global_stats_url,
+    personal_stats_url,
+    private_leaderboard_url,
+    LEADERBOARD_REFRESH,
+    MAX_RETRIES,
+    TOKEN_FILE,
+    WAIT_TIME,
 };
-use crate::data::{base_url, DATA_DIR, WAIT_TIME};
 use crate::internal_util::{
+    check_input,
+    finished_run,
+    is_bench_mode,
+    is_fresh,
+    is_logged_out,
+    is_offline,
     is_practice_mode,
+    is_verify_mode,
     message_from_body,
     must_run_solutions,
     open_page,
+    pre_from_body,
     pretty_print,
     print_rank,
+    read_stale,
+    report_skipped,
+    report_verification,
+    skip_bench,
     strip_trailing_nl,
+    time_limit,
+    warn_stale,
+    Benchmark,
+    InputProblem,
+    SendError,
     Submissions,
 };
-use crate::MaybeDisplay;
+use crate::queue::QueuedAnswer;
+use crate::timings::{print_run_timings, Stopwatch};
+use crate::{
+    Calendar,
+    GlobalStats,
+    MaybeDisplay,
+    PartOutcome,
+    PersonalStats,
+    PrivateLeaderboard,
+};
 
 async fn wait_for_unlock(
     now: DateTime<Utc>,
@@ -59,11 +124,11 @@ fn fetch_impl(
     never_print: bool,
 ) -> Pin<Box<dyn Future<Output = String>>> {
     Box::pin(async move {
+        migrate().await;
         assert!(year >= 2015, "Invalid year");
         assert!((1..=25).contains(&day), "Invalid day");
-        let in_folder = &*DATA_DIR / year.to_string();
-        make(&in_folder).await;
-        let in_file = &in_folder / format!("{day}.in");
+        make(&year_dir(year)).await;
+        let in_file = input_file(year, day);
 
         if in_file.exists() {
             let should_print = if is_practice_mode() {
@@ -76,25 +141,46 @@ fn fetch_impl(
             } else {
                 false
             };
-            let input = fs::read_to_string(&in_file)
+            let input = match fs::read_to_string(&in_file)
                 .await
-                .map_or_else(
-                    |_| -> Pin<Box<dyn Future<Output = _>>> {
-                        Box::pin(async {
-                            fs::remove_file(in_file)
-                                .await
-                                .expect("Removing the input file should not fail");
-                            fetch(day, year, true).await
-                        })
-                    },
-                    |s| Box::pin(async move { s }),
+                .map_err(|_| "the input could not be read".to_string())
+                .and_then(|input| verify_input(&in_file, &input).map(|()| input))
+            {
+                Ok(input) => input,
+                Err(problem) => {
+                    eprintln!(
+                        "{} {problem}. {}",
+                        "Warning: The cached input is invalid:".red(),
+                        "Fetching it again...".red()
+                    );
+                    fs::remove_file(&in_file)
+                        .await
+                        .expect("Removing the input file should not fail");
+                    fetch(day, year, true).await
+                },
+            };
+            if !input_metadata_file(&in_file).exists() {
+                // cached before metadata was recorded; the best guess at when
+                // it was fetched is when it was written
+                let fetched_at = fs::metadata(&in_file)
+                    .await
+                    .and_then(|metadata| metadata.modified())
+                    .unwrap_or_else(|_| SystemTime::now());
+                let _ = write_atomic(
+                    &input_metadata_file(&in_file),
+                    InputMetadata::new(&input, fetched_at).to_json(),
                 )
                 .await;
+            }
             if should_print && !never_print && is_practice_mode() {
                 println!("{input}");
             }
             input
         } else {
+            assert!(
+                !is_offline(),
+                "The input for day {day} of {year} isn't cached, and raoc is offline."
+            );
             let mut unlock = Utc
                 .with_ymd_and_hms(year, 12, day, 5, 0, 0)
                 .single()
@@ -112,8 +198,15 @@ fn fetch_impl(
                 // On the first day, run a stray request to validate the user's
                 // token
                 if day == 1 {
-                    let resp = get(&(base_url(year, day) + "/input"), true).await;
-                    if resp.status().is_client_error() {
+                    let resp = get(&(base_url(year, day) + "/input"), true, "fetch")
+                        .await
+                        .unwrap_or_else(|err| {
+                            panic!(
+                                "Couldn't fetch the input for day {day} of {year}, as \
+                                 {err}."
+                            )
+                        });
+                    if resp.status.is_client_error() {
                         load_token_from_stdin(
                             "Your token has expired. Please enter your new token."
                                 .red(),
@@ -125,30 +218,32 @@ fn fetch_impl(
                 }
                 wait_for_unlock(now, unlock, year, day).await;
             }
-            let resp = get(&(base_url(year, day) + "/input"), true).await;
-            if !resp.status().is_success() {
-                if resp.status().is_client_error() {
-                    load_token_from_stdin(
-                        "Your token has expired. Please enter your new token.".red(),
+            let resp = get(&(base_url(year, day) + "/input"), true, "fetch")
+                .await
+                .unwrap_or_else(|err| {
+                    panic!(
+                        "Couldn't fetch the input for day {day} of {year}, as {err}."
                     )
-                    .await;
-                    return fetch(day, year, never_print).await;
-                }
-                panic!("Received bad response from server: {}", resp.status());
+                });
+            let status = resp.status;
+            let input = strip_trailing_nl(resp.body);
+            let problem = check_input(&input).err();
+            if problem == Some(InputProblem::LoggedOut)
+                || (status.is_client_error() && problem != Some(InputProblem::TooEarly))
+            {
+                load_token_from_stdin(
+                    "Your token has expired. Please enter your new token.".red(),
+                )
+                .await;
+                return fetch(day, year, never_print).await;
             }
-            let input = strip_trailing_nl(
-                resp.text()
-                    .await
-                    .unwrap_or_else(|_| unreachable!("Response should be text")),
-            );
-            fs::write(in_file, &input).await.unwrap_or_else(|_| {
-                eprintln!(
-                    "{}",
-                    "Warning: Failed to cache input file. Please check your \
-                     permissions."
-                        .red()
-                );
-            });
+            if !status.is_success() && problem != Some(InputProblem::TooEarly) {
+                panic!("Received bad response from server: {status}");
+            }
+            if let Some(problem) = problem {
+                panic!("Advent of Code did not send back a puzzle input: {problem}");
+            }
+            cache_input(&in_file, &input).await;
             if !never_print {
                 println!("{input}");
             }
@@ -157,6 +252,26 @@ fn fetch_impl(
     })
 }
 
+/// Cache a freshly-fetched input, along with its metadata.
+async fn cache_input(in_file: &Path, input: &str) {
+    let cached = match write_atomic(in_file, input).await {
+        Ok(()) => {
+            write_atomic(
+                &input_metadata_file(in_file),
+                InputMetadata::new(input, SystemTime::now()).to_json(),
+            )
+            .await
+        },
+        Err(err) => Err(err),
+    };
+    if cached.is_err() {
+        eprintln!(
+            "{}",
+            "Warning: Failed to cache input file. Please check your permissions.".red()
+        );
+    }
+}
+
 /// Fetch and return the input for `day` of `year`.
 ///
 /// If `--practice` is provided on the command line, pretend that today is the
@@ -175,6 +290,143 @@ pub async fn fetch(day: u32, year: i32, never_print: bool) -> String {
     fetch_impl(day, year, never_print).await
 }
 
+/// Download the input for every unlocked day of `year` that isn't already
+/// cached, waiting at least `min_delay` between requests.
+///
+/// Returns the days fetched. See [`fetch_years`] for details.
+pub async fn fetch_year(year: i32, min_delay: Duration) -> Vec<u32> {
+    fetch_years([year], min_delay)
+        .await
+        .into_iter()
+        .map(|(_, day)| day)
+        .collect()
+}
+
+/// Download the input for every unlocked day of each of `years` that isn't
+/// already cached, waiting at least `min_delay` between requests.
+///
+/// Unlike [`fetch`], this never asks for a token: it stops if none has been
+/// saved, or at the first authentication error, so it's safe to leave
+/// unattended. A day whose input looks wrong is skipped. Returns the
+/// `(year, day)` of each input fetched.
+///
+/// # Panics
+///
+/// If any year is before 2015, or if Advent of Code sends back a server error.
+pub async fn fetch_years(
+    years: impl IntoIterator<Item = i32>,
+    min_delay: Duration,
+) -> Vec<(i32, u32)> {
+    migrate().await;
+    let mut fetched = Vec::new();
+    let mut last_request: Option<Instant> = None;
+    'years: for year in years {
+        assert!(year >= 2015, "Invalid year");
+        make(&year_dir(year)).await;
+        for day in 1..=25 {
+            let unlock = Utc
+                .with_ymd_and_hms(year, 12, day, 5, 0, 0)
+                .single()
+                .unwrap_or_else(|| {
+                    unreachable!("December days at 5AM are always valid")
+                });
+            if Utc::now() < unlock {
+                break;
+            }
+            let in_file = input_file(year, day);
+            if in_file.exists() {
+                continue;
+            }
+            if is_offline() {
+                eprintln!(
+                    "{}",
+                    "raoc is offline, so no inputs can be fetched; stopping.".yellow()
+                );
+                break 'years;
+            }
+            if !TOKEN_FILE.exists() {
+                // asking for a token would block an unattended fetch
+                eprintln!(
+                    "{}",
+                    "No token has been saved; stopping. Run any solution to enter \
+                     your token, then try again."
+                        .red()
+                );
+                break 'years;
+            }
+            if let Some(remaining) = last_request
+                .and_then(|last_request| min_delay.checked_sub(last_request.elapsed()))
+            {
+                wait("Waiting before the next request...".yellow(), remaining).await;
+            }
+
+            let resp = match get(&(base_url(year, day) + "/input"), true, "fetch_years")
+                .await
+            {
+                Ok(resp) => resp,
+                Err(err) => {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "Couldn't fetch day {day} of {year}, as {err}; stopping."
+                        )
+                        .red()
+                    );
+                    break 'years;
+                },
+            };
+            last_request = Some(Instant::now());
+            let status = resp.status;
+            let input = strip_trailing_nl(resp.body);
+            match check_input(&input) {
+                _ if status == StatusCode::NOT_FOUND => {
+                    // later days of this year don't exist either
+                    eprintln!(
+                        "{} {} {}",
+                        "Day".yellow(),
+                        style(day).blue(),
+                        format!("of {year} does not exist; moving on.").yellow()
+                    );
+                    continue 'years;
+                },
+                Err(InputProblem::LoggedOut) => {},
+                _ if status.is_client_error() => {},
+                _ if !status.is_success() => {
+                    panic!("Received bad response from server: {status}")
+                },
+                Err(problem) => {
+                    eprintln!(
+                        "{} {}{}",
+                        format!("Day {day} of {year} was not fetched:").red(),
+                        problem.to_string().red(),
+                        "; moving on.".red()
+                    );
+                    continue;
+                },
+                Ok(()) => {
+                    cache_input(&in_file, &input).await;
+                    println!(
+                        "{} {} {}",
+                        "Fetched day".green(),
+                        style(day).blue(),
+                        format!("of {year}.").green()
+                    );
+                    fetched.push((year, day));
+                    continue;
+                },
+            }
+            eprintln!(
+                "{}",
+                "Your token has expired; stopping. Run any solution to enter a new \
+                 token, then try again."
+                    .red()
+            );
+            break 'years;
+        }
+    }
+    fetched
+}
+
 /// Submit a solution.
 ///
 /// Submissions are cached; submitting an already-submitted solution will return
@@ -187,6 +439,30 @@ pub async fn submit(day: u32, part: u32, year: i32, answer: impl Display) {
     submit_impl(day, part, year, answer.to_string()).await;
 }
 
+/// Submit every answer in the submission queue (answers which couldn't be
+/// delivered when they were first submitted), oldest first, stopping if Advent
+/// of Code still can't be reached.
+///
+/// Queued answers are submitted as if by [`submit`], so answers to parts which
+/// have been solved since are not sent, and cooldowns are respected.
+///
+/// Returns the answers which are still queued.
+pub async fn flush_submissions() -> Vec<QueuedAnswer> {
+    for queued in queued_answers().await {
+        let delivered = if queued.day == 25 && queued.part == 2 {
+            submit_25(queued.year).await
+        } else {
+            submit_impl(queued.day, queued.part, queued.year, queued.answer.clone())
+                .await
+        };
+        if !delivered {
+            break;
+        }
+        dequeue(&queued).await;
+    }
+    queued_answers().await
+}
+
 async fn submit_already_solved(
     solution: &str,
     answer: &str,
@@ -215,7 +491,23 @@ async fn submit_already_solved(
         style(part).blue(),
         solution.blue(),
     );
-    print_rank(&part_solutions[solution]);
+    if is_offline() {
+        if solution == answer {
+            println!("{}", "Your answer matches the cached solution.".green());
+        } else {
+            println!(
+                "{} {} {}",
+                "Unverified:".yellow(),
+                answer.blue(),
+                "does not match the cached solution, and can't be submitted while \
+                 raoc is offline."
+                    .yellow()
+            );
+        }
+    }
+    if let Some(response) = part_solutions.get(solution) {
+        print_rank(response);
+    }
 }
 
 async fn delay(msg: &str) -> bool {
@@ -247,15 +539,31 @@ async fn delay(msg: &str) -> bool {
     }
 }
 
-async fn submit_impl(day: u32, part: u32, year: i32, answer: String) {
-    let submission_dir = &*DATA_DIR / year.to_string() / day.to_string();
-    make(&submission_dir).await;
-    let submissions = &submission_dir / "submissions.txt";
+/// Returns `false` if the answer couldn't be delivered, and was queued instead.
+async fn submit_impl(day: u32, part: u32, year: i32, answer: String) -> bool {
+    migrate().await;
+    make(&day_dir(year, day)).await;
+    let submissions = submissions_file(year, day);
     let mut solutions = if submissions.exists() {
-        serde_json::from_str(&fs::read_to_string(&submissions).await.unwrap_or_else(
-            |_| unreachable!("Failed to read existent submission cache"),
-        ))
-        .unwrap_or_else(|_| unreachable!("File should be valid"))
+        if let Some(solutions) = read_submissions(&submissions) {
+            solutions
+        } else {
+            let backup = day_dir(year, day) / format!("{SUBMISSIONS}.corrupt");
+            eprintln!(
+                "{} {}",
+                "Warning: Failed to parse the submission cache. A new one will be \
+                 started, and the old one has been moved to"
+                    .red(),
+                backup.display(),
+            );
+            fs::rename(&submissions, backup)
+                .await
+                .expect("Moving the corrupt submission cache failed");
+            Submissions {
+                part_1: HashMap::new(),
+                part_2: HashMap::new(),
+            }
+        }
     } else {
         Submissions {
             part_1: HashMap::new(),
@@ -268,7 +576,7 @@ async fn submit_impl(day: u32, part: u32, year: i32, answer: String) {
         _ => unreachable!("Part should be 1 or 2"),
     };
 
-    let solution_file = &submission_dir / format!("{part}.solution");
+    let solution_file = solution_file(year, day, part);
     #[allow(clippy::map_entry)]
     if solution_file.exists() {
         let solution = fs::read_to_string(solution_file)
@@ -286,38 +594,69 @@ async fn submit_impl(day: u32, part: u32, year: i32, answer: String) {
             "has already been submitted.\nResponse was:".yellow(),
         );
         pretty_print(part_solutions[&answer].as_str());
+    } else if is_offline() {
+        println!(
+            "{} {} {}",
+            "Unverified:".yellow(),
+            answer.as_str().blue(),
+            "was not submitted, as raoc is offline.".yellow()
+        );
+        enqueue(year, day, part, &answer).await;
+        return false;
     } else {
         let mut msg;
+        let mut uncertain_attempts = 0;
         loop {
             println!(
                 "Submitting {} as the solution to part {}...",
                 answer.as_str().blue(),
                 style(part).blue()
             );
-            let resp = post(
+            let resp = match post(
                 &(base_url(year, day) + "/answer"),
                 true,
                 HashMap::from([
                     ("level", part.to_string()),
                     ("answer", answer.to_string()),
                 ]),
+                "submit",
             )
-            .await;
-            if !resp.status().is_success() {
-                if resp.status().is_client_error() {
+            .await
+            {
+                Ok(resp) => resp,
+                Err(SendError::MaybeSent) if uncertain_attempts < MAX_RETRIES => {
+                    match check_answer_accepted(day, part, year, &answer).await {
+                        Some(true) => {
+                            msg = "That's the right answer!".to_string();
+                            break;
+                        },
+                        Some(false) => {
+                            uncertain_attempts += 1;
+                            continue;
+                        },
+                        None => {
+                            enqueue(year, day, part, &answer).await;
+                            return false;
+                        },
+                    }
+                },
+                Err(_) => {
+                    enqueue(year, day, part, &answer).await;
+                    return false;
+                },
+            };
+            if !resp.status.is_success() {
+                if resp.status.is_client_error() {
                     load_token_from_stdin(
                         "Your token has expired. Please enter your new token.".red(),
                     )
                     .await;
                     continue;
                 }
-                panic!("Received bad response from server: {}", resp.status());
+                panic!("Received bad response from server: {}", resp.status);
             }
 
-            let resp_text = resp
-                .text()
-                .await
-                .unwrap_or_else(|_| unreachable!("Response should be text"));
+            let resp_text = resp.body;
             msg = message_from_body(&resp_text);
             if !delay(&msg).await {
                 break;
@@ -325,7 +664,7 @@ async fn submit_impl(day: u32, part: u32, year: i32, answer: String) {
         }
         if msg.starts_with("That's the") {
             print_rank(&msg);
-            fs::write(solution_file, &answer)
+            write_atomic(&solution_file, &answer)
                 .await
                 .expect("Writing solution cache failed");
             calculate_practice_result(day, part, year).await;
@@ -336,44 +675,79 @@ async fn submit_impl(day: u32, part: u32, year: i32, answer: String) {
             pretty_print(&msg);
         }
 
-        part_solutions.insert(answer, msg);
-        fs::write(submissions, serde_json::to_string(&solutions).unwrap())
+        let _lock = lock(&submissions).await;
+        // another process may have submitted an answer since the cache was read
+        let mut solutions = read_submissions(&submissions).unwrap_or(solutions);
+        match part {
+            1 => &mut solutions.part_1,
+            2 => &mut solutions.part_2,
+            _ => unreachable!("Part should be 1 or 2"),
+        }
+        .insert(answer, msg);
+        write_atomic(&submissions, serde_json::to_string(&solutions).unwrap())
             .await
             .expect("Writing submissions cache failed");
     }
+    true
 }
 
-async fn submit_25(year: &str) {
-    let resp = loop {
+/// Returns `false` if the request couldn't be delivered, and was queued
+/// instead.
+async fn submit_25(year: i32) -> bool {
+    migrate().await;
+    let mut uncertain_attempts = 0;
+    let msg = loop {
         println!(
             "{} {}{}",
             "Finishing Advent of Code".green(),
-            year.blue(),
+            style(year).blue(),
             '!'.green(),
         );
-        let resp = post(
+        let resp = match post(
             &(base_url(year, 25) + "/answer"),
             true,
             HashMap::from([("level", "2"), ("answer", "0")]),
+            "submit",
         )
-        .await;
-        if resp.status().is_success() {
-            break resp;
-        } else if resp.status().is_client_error() {
+        .await
+        {
+            Ok(resp) => resp,
+            Err(SendError::MaybeSent) if uncertain_attempts < MAX_RETRIES => {
+                match check_answer_accepted(25, 2, year, "").await {
+                    Some(true) => {
+                        break "Congratulations! You've finished every puzzle."
+                            .to_string();
+                    },
+                    Some(false) => {
+                        uncertain_attempts += 1;
+                        continue;
+                    },
+                    None => {
+                        enqueue(year, 25, 2, "").await;
+                        return false;
+                    },
+                }
+            },
+            Err(_) => {
+                enqueue(year, 25, 2, "").await;
+                return false;
+            },
+        };
+        if resp.status.is_success() {
+            break message_from_body(&resp.body);
+        } else if resp.status.is_client_error() {
             load_token_from_stdin(
                 "Your token has expired. Please enter your new token.".red(),
             )
             .await;
         } else {
-            panic!("Received bad response from server: {}", resp.status());
+            panic!("Received bad response from server: {}", resp.status);
         }
     };
 
     println!("Response from the server:");
-    println!(
-        "{}",
-        message_from_body(&resp.text().await.expect("Response should be text")),
-    );
+    println!("{msg}");
+    true
 }
 
 /// Run the functions only if we haven't seen a solution.
@@ -381,6 +755,10 @@ async fn submit_25(year: &str) {
 /// Will also run solutions if `--force-run` or `--practice` is passed on the
 /// command line.
 ///
+/// See [`lazy_submit_part`] for the `--verify` mode, the time limit, and how
+/// panics are handled; the outcome of each part is returned. To support
+/// `--bench`, use [`lazy_submit_benchable`] instead.
+///
 /// The solution for part 2 will be ignored if day is 25.
 pub async fn lazy_submit<
     U,
@@ -393,9 +771,33 @@ pub async fn lazy_submit<
     solution_part_1: impl FnOnce(U) -> S1,
     solution_part_2: impl FnOnce(U) -> S2,
     mut parse_raw: impl FnMut(&str) -> V,
-) {
-    lazy_submit_part(day, year, 1, solution_part_1, &mut parse_raw).await;
-    lazy_submit_part(day, year, 2, solution_part_2, &mut parse_raw).await;
+) -> [PartOutcome; 2] {
+    [
+        lazy_submit_part(day, year, 1, solution_part_1, &mut parse_raw).await,
+        lazy_submit_part(day, year, 2, solution_part_2, &mut parse_raw).await,
+    ]
+}
+
+/// Like [`lazy_submit`], but if `--bench` is passed on the command line, each
+/// part is benchmarked instead; see [`lazy_submit_part_benchable`].
+///
+/// The solution for part 2 will be ignored if day is 25.
+pub async fn lazy_submit_benchable<
+    U,
+    S1: Future<Output = impl MaybeDisplay>,
+    S2: Future<Output = impl MaybeDisplay>,
+    V: Future<Output = U>,
+>(
+    day: u32,
+    year: i32,
+    solution_part_1: impl Fn(U) -> S1,
+    solution_part_2: impl Fn(U) -> S2,
+    mut parse_raw: impl FnMut(&str) -> V,
+) -> [PartOutcome; 2] {
+    [
+        lazy_submit_part_benchable(day, year, 1, solution_part_1, &mut parse_raw).await,
+        lazy_submit_part_benchable(day, year, 2, solution_part_2, &mut parse_raw).await,
+    ]
 }
 
 /// Run the function only if we haven't seen a solution.
@@ -403,6 +805,29 @@ pub async fn lazy_submit<
 /// Will also run solution if `--force-run` or `--practice` is passed on the
 /// command line.
 ///
+/// If `--verify` is passed on the command line, the solution is run against
+/// the cached input, and its answer is compared with the cached accepted
+/// answer instead of being submitted; no requests are made, but the run is
+/// timed and recorded as usual.
+///
+/// Benchmarking needs to run the solution repeatedly, so if `--bench` is
+/// passed on the command line, the part is skipped; use
+/// [`lazy_submit_part_benchable`] to benchmark it instead.
+///
+/// In offline mode, a part which has already been solved is run as if
+/// `--verify` had been passed, and its answer compared with the cached
+/// solution; new answers are queued to be submitted later.
+///
+/// If `--time-limit=SECONDS` is passed on the command line (or
+/// `time_limit.txt` in the data directory holds a number of seconds), a
+/// solution which runs for longer is cancelled, and nothing is submitted (or
+/// verified); see [`work_with_time_limit`].
+///
+/// If the input parser or the solution panics, the panic is reported (along
+/// with how long the solution had been running) in place of an answer. The
+/// returned [`PartOutcome`] says whether the solution panicked or timed out;
+/// panics which abort the process can't be caught.
+///
 /// If the day is 25 and the part is 2, will ignore the solution.
 pub async fn lazy_submit_part<
     U,
@@ -415,69 +840,593 @@ pub async fn lazy_submit_part<
     part: u32,
     solution_part_1: impl FnOnce(U) -> S,
     parse_raw: impl FnOnce(&str) -> V,
-) {
-    let submission_dir = &*DATA_DIR / year.to_string() / day.to_string();
-    make(&submission_dir).await;
+) -> PartOutcome {
+    if is_bench_mode() && !is_verify_mode() {
+        return skip_bench(day, part, "lazy_submit_benchable");
+    }
+    submit_part(day, year, part, solution_part_1, parse_raw).await
+}
+
+/// Like [`lazy_submit_part`], but if `--bench` is passed on the command line,
+/// the input is parsed and the solution run on the cached input repeatedly,
+/// and timings for each are reported; nothing is submitted, and no requests
+/// are made.
+///
+/// If the day is 25 and the part is 2, will ignore the solution.
+pub async fn lazy_submit_part_benchable<
+    U,
+    M: MaybeDisplay,
+    S: Future<Output = M>,
+    V: Future<Output = U>,
+>(
+    day: u32,
+    year: i32,
+    part: u32,
+    solution_part_1: impl Fn(U) -> S,
+    parse_raw: impl FnMut(&str) -> V,
+) -> PartOutcome {
+    if is_bench_mode() && !is_verify_mode() {
+        return bench_part(day, year, part, solution_part_1, parse_raw).await;
+    }
+    submit_part(day, year, part, solution_part_1, parse_raw).await
+}
+
+/// Run the solution, and submit (or verify) its answer.
+async fn submit_part<
+    U,
+    M: MaybeDisplay,
+    S: Future<Output = M>,
+    V: Future<Output = U>,
+>(
+    day: u32,
+    year: i32,
+    part: u32,
+    solution_part_1: impl FnOnce(U) -> S,
+    parse_raw: impl FnOnce(&str) -> V,
+) -> PartOutcome {
+    migrate().await;
+    if is_verify_mode() {
+        return verify_part(day, year, part, solution_part_1, parse_raw).await;
+    }
+    if is_offline()
+        && !is_practice_mode()
+        && !(day == 25 && part == 2)
+        && solution_file(year, day, part).exists()
+    {
+        // nothing can be submitted, but the answer can still be checked
+        println!(
+            "{} {}",
+            "Unverified:".yellow(),
+            "raoc is offline, so the answer will only be compared with the cached \
+             solution."
+                .yellow()
+        );
+        return verify_part(day, year, part, solution_part_1, parse_raw).await;
+    }
+    make(&day_dir(year, day)).await;
     if day == 25 && part == 2 {
         // don't try to submit part 2 if part 1 isn't solved
-        if (&submission_dir / "1.solution").exists() {
-            submit_25(&year.to_string()).await;
+        if solution_file(year, day, 1).exists() {
+            submit_25(year).await;
         } else {
-            return;
+            return PartOutcome::Finished;
         }
     }
-    let solution_file = &submission_dir / format!("{part}.solution");
+    let solution_file = solution_file(year, day, part);
     if !solution_file.exists()
         || must_run_solutions()
         || (is_practice_mode()
             && practice_result_for(day, year).await.1.len() < part as usize)
     {
-        let answer = work(
+        let start = Instant::now();
+        let raw = fetch(day, year, false).await;
+        let fetch_time = start.elapsed();
+        let time_limit = time_limit();
+        let start = Instant::now();
+        let result = work_with_time_limit(
             format!(
                 "{} {} {}",
                 "Running part".yellow(),
                 style(part).blue(),
                 "solution".yellow(),
             ),
-            async {
-                let raw = fetch(day, year, false).await;
-                solution_part_1(parse_raw(&raw).await).await
-            },
+            time_limit,
+            catch_panic(async {
+                let stopwatch = Stopwatch::start();
+                let parsed = parse_raw(&raw).await;
+                let parse = stopwatch.stop();
+                let stopwatch = Stopwatch::start();
+                let answer = solution_part_1(parsed).await;
+                (answer, parse, stopwatch.stop())
+            }),
         )
-        .await
-        .into_solution();
+        .await;
+        let (answer, parse, solve) =
+            match finished_run(day, part, start, time_limit, result) {
+                Ok(run) => run,
+                Err(outcome) => return outcome,
+            };
+        print_run_timings(fetch_time, &parse, &solve);
+        record_run(year, day, part, fetch_time, &parse, &solve).await;
+        let answer = answer.into_solution();
         if let Some(answer) = answer {
             submit(day, part, year, answer).await;
         }
     } else {
-        // load cached solutions
-        let submissions = submission_dir / "submissions.json";
-        let solutions: Submissions = serde_json::from_str(
-            &fs::read_to_string(submissions).await.unwrap_or_else(|_| {
-                unreachable!(
-                    "Failed to read submission cache, which must exist as this part \
-                     has already been solved"
-                )
-            }),
-        )
-        .unwrap_or_else(|_| unreachable!("Failed to parse submission cache"));
-
         let solution = fs::read_to_string(solution_file)
             .await
-            .unwrap_or_else(|_| unreachable!("Solution file was corrupt"));
-        let response = match part {
-            1 => &solutions.part_1[&solution],
-            2 => &solutions.part_2[&solution],
-            _ => unreachable!("Part should be 1 or 2"),
-        };
+            .expect("Failed to read solution cache");
         println!(
             "Day {} part {} has already been solved.\nThe solution was {}",
             style(day).blue(),
             style(part).blue(),
-            solution.blue(),
+            solution.as_str().blue(),
         );
-        print_rank(response);
+        // load cached response
+        let response =
+            read_submissions(&submissions_file(year, day)).and_then(|mut solutions| {
+                match part {
+                    1 => solutions.part_1.remove(&solution),
+                    2 => solutions.part_2.remove(&solution),
+                    _ => unreachable!("Part should be 1 or 2"),
+                }
+            });
+        if let Some(response) = response {
+            print_rank(&response);
+        } else {
+            eprintln!(
+                "{}",
+                "Warning: No submission was recorded for this solution. Run `raoc \
+                 validate` to check your cache for problems."
+                    .yellow()
+            );
+        }
     }
+    PartOutcome::Finished
+}
+
+fn private_leaderboard_impl(
+    year: i32,
+    id: u64,
+) -> Pin<Box<dyn Future<Output = PrivateLeaderboard>>> {
+    Box::pin(async move {
+        migrate().await;
+        assert!(year >= 2015, "Invalid year");
+        make(&(year_dir(year) / PRIVATE_LEADERBOARDS)).await;
+        let cache_file = private_leaderboard_file(year, id);
+        if is_fresh(&cache_file, LEADERBOARD_REFRESH) {
+            let data = fs::read_to_string(&cache_file)
+                .await
+                .expect("Failed to read private leaderboard cache.");
+            // Advent of Code asks for leaderboards to be requested at most every
+            // 15 minutes, so don't refetch one just because its cache is broken
+            return serde_json::from_str(&data).unwrap_or_else(|_| {
+                panic!(
+                    "Failed to parse the cached private leaderboard {id}. Delete {} \
+                     to fetch it again.",
+                    cache_file.display()
+                )
+            });
+        }
+
+        let resp = match get(
+            &private_leaderboard_url(year, id),
+            true,
+            "private_leaderboard",
+        )
+        .await
+        {
+            Ok(resp) => resp,
+            Err(err) => {
+                let Some(leaderboard) = read_stale(&cache_file) else {
+                    panic!("Couldn't fetch private leaderboard {id}, as {err}.");
+                };
+                warn_stale(&format!("private leaderboard {id}"), &err);
+                return leaderboard;
+            },
+        };
+        if !resp.status.is_success() {
+            if resp.status == StatusCode::NOT_FOUND {
+                panic!(
+                    "Private leaderboard {id} does not exist or is not visible to you."
+                );
+            }
+            if resp.status.is_client_error() {
+                load_token_from_stdin(
+                    "Your token has expired. Please enter your new token.".red(),
+                )
+                .await;
+                return private_leaderboard(year, id).await;
+            }
+            panic!("Received bad response from server: {}", resp.status);
+        }
+        let data = resp.body;
+        // Advent of Code redirects to an HTML page if the leaderboard isn't
+        // visible to us, so make sure we got JSON before caching it
+        let leaderboard = serde_json::from_str(&data).unwrap_or_else(|_| {
+            panic!(
+                "Failed to parse private leaderboard {id}. Please check that it \
+                 exists and that you are a member of it."
+            )
+        });
+        write_atomic(&cache_file, &data).await.unwrap_or_else(|_| {
+            eprintln!(
+                "{}",
+                "Warning: Failed to cache private leaderboard. Please check your \
+                 permissions."
+                    .red()
+            );
+        });
+        leaderboard
+    })
+}
+
+/// Run the solution to `part` against the cached input, and compare its answer
+/// with the cached accepted answer.
+async fn verify_part<
+    U,
+    M: MaybeDisplay,
+    S: Future<Output = M>,
+    V: Future<Output = U>,
+>(
+    day: u32,
+    year: i32,
+    part: u32,
+    solution: impl FnOnce(U) -> S,
+    parse_raw: impl FnOnce(&str) -> V,
+) -> PartOutcome {
+    if day == 25 && part == 2 {
+        // there's no answer to check
+        return PartOutcome::Finished;
+    }
+    let Ok(expected) = fs::read_to_string(solution_file(year, day, part)).await else {
+        report_skipped(day, part, "it hasn't been solved");
+        return PartOutcome::Finished;
+    };
+    let start = Instant::now();
+    let Ok(raw) = fs::read_to_string(input_file(year, day)).await else {
+        report_skipped(day, part, "the input isn't cached");
+        return PartOutcome::Finished;
+    };
+    let fetch_time = start.elapsed();
+    let time_limit = time_limit();
+    let start = Instant::now();
+    let result = work_with_time_limit(
+        format!(
+            "{} {} {}",
+            "Verifying part".yellow(),
+            style(part).blue(),
+            "solution".yellow(),
+        ),
+        time_limit,
+        catch_panic(async {
+            let stopwatch = Stopwatch::start();
+            let parsed = parse_raw(&raw).await;
+            let parse = stopwatch.stop();
+            let stopwatch = Stopwatch::start();
+            let answer = solution(parsed).await;
+            (answer, parse, stopwatch.stop())
+        }),
+    )
+    .await;
+    let (answer, parse, solve) =
+        match finished_run(day, part, start, time_limit, result) {
+            Ok(run) => run,
+            Err(outcome) => return outcome,
+        };
+    record_run(year, day, part, fetch_time, &parse, &solve).await;
+    report_verification(
+        day,
+        part,
+        &expected,
+        answer.into_solution(),
+        parse.time + solve.time,
+    );
+    PartOutcome::Finished
+}
+
+/// Repeatedly parse the cached input and run the solution to `part` on it,
+/// reporting how long each takes.
+async fn bench_part<
+    U,
+    M: MaybeDisplay,
+    S: Future<Output = M>,
+    V: Future<Output = U>,
+>(
+    day: u32,
+    year: i32,
+    part: u32,
+    solution: impl Fn(U) -> S,
+    mut parse_raw: impl FnMut(&str) -> V,
+) -> PartOutcome {
+    migrate().await;
+    if day == 25 && part == 2 {
+        // there's no solution to run
+        return PartOutcome::Finished;
+    }
+    let Ok(raw) = fs::read_to_string(input_file(year, day)).await else {
+        report_skipped(day, part, "the input isn't cached");
+        return PartOutcome::Finished;
+    };
+    eprintln!(
+        "{} {}{}",
+        "Benchmarking part".yellow(),
+        style(part).blue(),
+        "...".yellow()
+    );
+    let mut bench = Benchmark::new();
+    let mut answer = None;
+    let start = Instant::now();
+    let result = catch_panic(async {
+        while bench.keep_going() {
+            let start = Instant::now();
+            let parsed = parse_raw(&raw).await;
+            let parse_time = start.elapsed();
+            let start = Instant::now();
+            let result = solution(parsed).await;
+            bench.record(parse_time, start.elapsed());
+            if answer.is_none() {
+                answer = Some(result);
+            } else {
+                black_box(result);
+            }
+        }
+    })
+    .await;
+    if let Err(outcome) = finished_run(day, part, start, None, Some(result)) {
+        return outcome;
+    }
+    bench.report(day, part, answer.and_then(MaybeDisplay::into_solution));
+    PartOutcome::Finished
+}
+
+/// Fetch the private leaderboard with the given `id` for `year`.
+///
+/// Leaderboards are cached in the data directory, and will not be re-requested
+/// until the cached copy is at least 15 minutes old, as requested by Advent of
+/// Code.
+///
+/// # Panics
+///
+/// If the year is invalid, or the leaderboard does not exist or is not visible
+/// to you.
+#[must_use]
+pub async fn private_leaderboard(year: i32, id: u64) -> PrivateLeaderboard {
+    private_leaderboard_impl(year, id).await
+}
+
+fn personal_stats_impl(year: i32) -> Pin<Box<dyn Future<Output = PersonalStats>>> {
+    Box::pin(async move {
+        migrate().await;
+        assert!(year >= 2015, "Invalid year");
+        make(&year_dir(year)).await;
+        let cache_file = year_dir(year) / PERSONAL_STATS;
+        if is_fresh(&cache_file, LEADERBOARD_REFRESH) {
+            let data = fs::read_to_string(&cache_file)
+                .await
+                .expect("Failed to read personal statistics cache.");
+            if let Ok(stats) = serde_json::from_str(&data) {
+                return stats;
+            }
+        }
+
+        let resp = match get(&personal_stats_url(year), true, "personal_stats").await {
+            Ok(resp) => resp,
+            Err(err) => {
+                let Some(stats) = read_stale(&cache_file) else {
+                    panic!("Couldn't fetch your statistics for {year}, as {err}.");
+                };
+                warn_stale(&format!("your statistics for {year}"), &err);
+                return stats;
+            },
+        };
+        if !resp.status.is_success() {
+            if resp.status.is_client_error() {
+                load_token_from_stdin(
+                    "Your token has expired. Please enter your new token.".red(),
+                )
+                .await;
+                return personal_stats(year).await;
+            }
+            panic!("Received bad response from server: {}", resp.status);
+        }
+        let page = resp.body;
+        let stats = match pre_from_body(&page) {
+            Some(table) => PersonalStats::parse(year, &table),
+            None if is_logged_out(&page) => {
+                load_token_from_stdin(
+                    "Your token has expired. Please enter your new token.".red(),
+                )
+                .await;
+                return personal_stats(year).await;
+            },
+            // No stars collected yet
+            None => PersonalStats::parse(year, ""),
+        };
+        write_atomic(
+            &cache_file,
+            serde_json::to_string(&stats).expect("Serialising should never fail"),
+        )
+        .await
+        .unwrap_or_else(|_| {
+            eprintln!(
+                "{}",
+                "Warning: Failed to cache personal statistics. Please check your \
+                 permissions."
+                    .red()
+            );
+        });
+        stats
+    })
+}
+
+/// Fetch your personal statistics for `year`.
+///
+/// These include your time, rank and score for every part you have solved,
+/// even if you did not make the global leaderboard. Statistics are cached in
+/// the data directory, and will not be re-requested until the cached copy is
+/// at least 15 minutes old.
+///
+/// # Panics
+///
+/// If the year is invalid.
+#[must_use]
+pub async fn personal_stats(year: i32) -> PersonalStats {
+    personal_stats_impl(year).await
+}
+
+fn calendar_impl(year: i32) -> Pin<Box<dyn Future<Output = Calendar>>> {
+    Box::pin(async move {
+        migrate().await;
+        assert!(year >= 2015, "Invalid year");
+        make(&year_dir(year)).await;
+        let cache_file = year_dir(year) / CALENDAR;
+        if is_fresh(&cache_file, LEADERBOARD_REFRESH) {
+            let data = fs::read_to_string(&cache_file)
+                .await
+                .expect("Failed to read calendar cache.");
+            if let Ok(calendar) = serde_json::from_str::<Calendar>(&data) {
+                return calendar.merge(&Calendar::from_cache(year));
+            }
+        }
+
+        let resp = match get(&calendar_url(year), true, "calendar").await {
+            Ok(resp) => resp,
+            Err(err) => {
+                // the cached solutions are enough to fill the calendar in
+                warn_stale(&format!("the calendar for {year}"), &err);
+                let cached = Calendar::from_cache(year);
+                return match read_stale::<Calendar>(&cache_file) {
+                    Some(calendar) => calendar.merge(&cached),
+                    None => cached,
+                };
+            },
+        };
+        if !resp.status.is_success() {
+            if resp.status.is_client_error() {
+                load_token_from_stdin(
+                    "Your token has expired. Please enter your new token.".red(),
+                )
+                .await;
+                return calendar(year).await;
+            }
+            panic!("Received bad response from server: {}", resp.status);
+        }
+        let page = resp.body;
+        if is_logged_out(&page) {
+            load_token_from_stdin(
+                "Your token has expired. Please enter your new token.".red(),
+            )
+            .await;
+            return calendar(year).await;
+        }
+        let calendar = Calendar::parse(year, &page);
+        write_atomic(
+            &cache_file,
+            serde_json::to_string(&calendar).expect("Serialising should never fail"),
+        )
+        .await
+        .unwrap_or_else(|_| {
+            eprintln!(
+                "{}",
+                "Warning: Failed to cache calendar. Please check your permissions."
+                    .red()
+            );
+        });
+        calendar.merge(&Calendar::from_cache(year))
+    })
+}
+
+/// Fetch the calendar for `year`, showing how many stars you have collected
+/// for each unlocked day.
+///
+/// The calendar is cached in the data directory, and will not be re-requested
+/// until the cached copy is at least 15 minutes old. Stars for solutions that
+/// have been cached since then are included anyway.
+///
+/// # Panics
+///
+/// If the year is invalid.
+#[must_use]
+pub async fn calendar(year: i32) -> Calendar {
+    calendar_impl(year).await
+}
+
+fn global_stats_impl(
+    year: i32,
+) -> Pin<Box<dyn Future<Output = Result<GlobalStats, String>>>> {
+    Box::pin(async move {
+        migrate().await;
+        assert!(year >= 2015, "Invalid year");
+        make(&year_dir(year)).await;
+        let cache_file = year_dir(year) / GLOBAL_STATS;
+        if is_fresh(&cache_file, LEADERBOARD_REFRESH) {
+            let data = fs::read_to_string(&cache_file)
+                .await
+                .expect("Failed to read global statistics cache.");
+            if let Ok(stats) = serde_json::from_str(&data) {
+                return Ok(stats);
+            }
+        }
+
+        let resp = match get(&global_stats_url(year), false, "global_stats").await {
+            Ok(resp) => resp,
+            Err(err) => {
+                let Some(stats) = read_stale(&cache_file) else {
+                    return Err(format!(
+                        "Couldn't fetch the global statistics for {year}, as {err}."
+                    ));
+                };
+                warn_stale(&format!("the global statistics for {year}"), &err);
+                return Ok(stats);
+            },
+        };
+        if !resp.status.is_success() {
+            return Err(format!(
+                "Received bad response from server: {}",
+                resp.status
+            ));
+        }
+        let page = resp.body;
+        let stats = GlobalStats::parse(year, &pre_from_body(&page).unwrap_or_default());
+        write_atomic(
+            &cache_file,
+            serde_json::to_string(&stats).expect("Serialising should never fail"),
+        )
+        .await
+        .unwrap_or_else(|_| {
+            eprintln!(
+                "{}",
+                "Warning: Failed to cache global statistics. Please check your \
+                 permissions."
+                    .red()
+            );
+        });
+        Ok(stats)
+    })
+}
+
+/// Fetch the global completion statistics for `year`.
+///
+/// These are the number of users who have solved each part of each day.
+/// Statistics are cached in the data directory, and will not be re-requested
+/// until the cached copy is at least 15 minutes old.
+///
+/// # Panics
+///
+/// If the year is invalid.
+#[must_use]
+pub async fn global_stats(year: i32) -> GlobalStats {
+    try_global_stats(year)
+        .await
+        .unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Fetch the global completion statistics for `year`, like [`global_stats`],
+/// but return an error instead of panicking if they can't be fetched and
+/// nothing is cached.
+pub(crate) async fn try_global_stats(year: i32) -> Result<GlobalStats, String> {
+    global_stats_impl(year).await
 }
 
 /// Retrieve the example input and answer for the specified puzzle.
@@ -523,10 +1472,10 @@ pub async fn get_sample_input(
     //     return part_tests.clone();
     // }
 
-    // let resp = get_text(&(base_url(year, day) + "/input"), true).await;
-    // let soup = tl::parse(&resp, tl::ParserOptions::new()).unwrap_or_else(|_|
-    // {     unreachable!("Advent of Code server returned a bad response")
-    // });
+    // let resp = get_text(&(base_url(year, day) + "/input"), true,
+    // "fetch").await; let soup = tl::parse(&resp,
+    // tl::ParserOptions::new()).unwrap_or_else(|_| {     unreachable!("
+    // Advent of Code server returned a bad response") });
 
     // let possible_test_inputs =
     // soup.query_selector("pre").unwrap_or_else(||unreachable!("Selector 'pre'
@@ -1,30 +1,59 @@
 use std::fmt::Display;
-use std::future::Future;
-use std::io;
+use std::future::{self, Future};
 use std::path::Path;
+use std::pin::pin;
+use std::task::Poll;
 use std::time::{Duration, Instant};
+use std::{io, panic};
 
 use chrono::{Datelike, NaiveDate, Utc};
 use crossterm::style::{style, Stylize};
 use lazy_static::lazy_static;
 use pathdiv::PathDiv;
-use reqwest::{header, Client, Response};
-use tokio::{fs, time};
+use reqwest::{header, Client, RequestBuilder, Response};
+use tokio::{fs, task, time};
 
+use super::interface::try_global_stats;
+use crate::audit::{self, RequestFailure};
+use crate::cache::{
+    day_dir,
+    ensure_migrated,
+    is_migrated,
+    leaderboards_file,
+    practice_dir,
+    temp_file,
+    CacheLock,
+};
 use crate::data::{
+    base_url,
     leaderboard_url,
-    DATA_DIR,
     GOLD,
-    PRACTICE_DATA_DIR,
+    MAX_RETRIES,
+    REQUEST_TIMEOUT,
     TOKEN_FILE,
     USER_AGENT,
 };
 use crate::internal_util::{
+    animate_spinners,
+    answer_accepted,
+    backoff,
+    duration_setting,
+    extrapolate_rank,
+    format_clock,
     format_time,
     get_leaderboard_time,
+    go_offline,
+    is_offline,
     is_practice_mode,
     strip_trailing_nl,
+    Reply,
+    SendError,
+    Spinner,
 };
+use crate::progress::{progress_status, reset_progress};
+use crate::queue::{self, QueuedAnswer};
+use crate::throttle;
+use crate::timings::{self, Stage};
 
 pub(crate) async fn load_token_from_stdin(why: impl Display) -> String {
     eprintln!("{why}");
@@ -34,84 +63,66 @@ pub(crate) async fn load_token_from_stdin(why: impl Display) -> String {
         .read_line(&mut token)
         .expect("Failed to read token.");
     token = strip_trailing_nl(token);
-    fs::write(&*TOKEN_FILE, &token)
+    write_atomic(&TOKEN_FILE, &token)
         .await
         .expect("Failed to write token file. Check your permissions.");
+    // pages fetched with the old token may just have been login pages
+    blocking(throttle::forget_successes).await;
     token
 }
 
 /// Wait the specified time, displaying a countdown, a spinner, and a message.
+///
+/// If stderr isn't a terminal, a status line is printed once a minute instead;
+/// pass `--spinner` or `--no-spinner` on the command line to choose either.
 pub async fn wait(msg: impl Display, time: Duration) {
     let start = Instant::now();
     let end = start + time;
     let mut time_left = end - start;
+    let mut spinner = Spinner::new();
     while {
-        eprint!(
-            "\r{} {} {:02}{}{:02}{}{:02}",
-            msg,
-            match (start.elapsed().as_millis() / 80) % 10 {
-                0 => '⠋',
-                1 => '⠙',
-                2 => '⠹',
-                3 => '⠸',
-                4 => '⠼',
-                5 => '⠴',
-                6 => '⠦',
-                7 => '⠧',
-                8 => '⠇',
-                9 => '⠏',
-                _ => unreachable!(),
-            }
-            .yellow(),
-            style(time_left.as_secs() / 3600).yellow(),
-            ':'.yellow(),
-            style(time_left.as_secs() / 60 % 60).yellow(),
-            ':'.yellow(),
-            style(time_left.as_secs() % 60).yellow(),
+        spinner.draw(
+            &msg,
+            start.elapsed(),
+            &format_clock(time_left).yellow().to_string(),
         );
         time_left.as_secs() > 0
     } {
         time::sleep(Duration::from_millis(100)).await;
         time_left = end - Instant::now();
     }
-    eprintln!();
+    spinner.finish();
+}
+
+/// The status shown by the spinner of [`work`]: the elapsed time, and the
+/// worker's progress if it has reported any.
+fn work_status(elapsed: Duration) -> String {
+    let clock = format_clock(elapsed).yellow();
+    match progress_status(elapsed) {
+        Some(progress) => format!("{clock} {progress}"),
+        None => clock.to_string(),
+    }
 }
 
 /// Run the given worker function, displaying a message, spinner, and elapsed
 /// timer.
+///
+/// If the worker reports its [`progress`](crate::progress()), the percentage
+/// done, rate and ETA are shown too. Like [`wait`], the spinner is replaced by
+/// a status line once a minute if stderr isn't a terminal.
 pub async fn work<T>(msg: impl Display, worker: impl Future<Output = T>) -> T {
+    let mut spinner = Spinner::new();
     tokio::select! {
         result = worker => {
-            eprintln!();
+            spinner.finish();
+            reset_progress();
             result
         },
         () = async {
             let start = Instant::now();
             loop {
                 let elapsed = start.elapsed();
-                eprint!(
-                    "\r{} {} {:02}{}{:02}{}{:02}",
-                    msg,
-                    match (start.elapsed().as_millis() / 80) % 10 {
-                        0 => '⠋',
-                        1 => '⠙',
-                        2 => '⠹',
-                        3 => '⠸',
-                        4 => '⠼',
-                        5 => '⠴',
-                        6 => '⠦',
-                        7 => '⠧',
-                        8 => '⠇',
-                        9 => '⠏',
-                        _ => unreachable!(),
-                    }
-                    .yellow(),
-                    style(elapsed.as_secs() / 3600).yellow(),
-                    ':'.yellow(),
-                    style(elapsed.as_secs() / 60 % 60).yellow(),
-                    ':'.yellow(),
-                    style(elapsed.as_secs() % 60).yellow(),
-                );
+                spinner.draw(&msg, elapsed, &work_status(elapsed));
                 tokio::time::sleep(Duration::from_millis(100)).await;
             }
         } => {
@@ -120,6 +131,46 @@ pub async fn work<T>(msg: impl Display, worker: impl Future<Output = T>) -> T {
     }
 }
 
+/// Run the given worker future like [`work`], but cancel it once it has run for
+/// `time_limit` (if given), returning `None`.
+///
+/// The worker can only be cancelled while it's waiting at an `.await`; one
+/// which never yields will run to completion regardless.
+pub async fn work_with_time_limit<T>(
+    msg: impl Display,
+    time_limit: Option<Duration>,
+    worker: impl Future<Output = T>,
+) -> Option<T> {
+    let Some(limit) = time_limit else {
+        return Some(work(msg, worker).await);
+    };
+    let result = time::timeout(limit, work(msg, worker)).await.ok();
+    if result.is_none() {
+        // `work` didn't get the chance to finish the spinner's line
+        if animate_spinners() {
+            eprintln!();
+        }
+        reset_progress();
+    }
+    result
+}
+
+/// Run the given worker future, catching an unwinding panic from any poll of it
+/// like [`catch_panic`](crate::internal_util::catch_panic).
+pub(crate) async fn catch_panic<T>(
+    worker: impl Future<Output = T>,
+) -> Result<T, String> {
+    let mut worker = pin!(worker);
+    future::poll_fn(|cx| {
+        match crate::internal_util::catch_panic(|| worker.as_mut().poll(cx)) {
+            Ok(Poll::Ready(result)) => Poll::Ready(Ok(result)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(message) => Poll::Ready(Err(message)),
+        }
+    })
+    .await
+}
+
 /// Make a directory, if it doesn't exist.
 pub(crate) async fn make(dir: &Path) {
     if !dir.exists() {
@@ -129,63 +180,312 @@ pub(crate) async fn make(dir: &Path) {
     }
 }
 
+/// Write `contents` to `path` atomically: readers (including other processes)
+/// see either the old contents or the new contents, never a truncated file.
+pub(crate) async fn write_atomic(
+    path: &Path,
+    contents: impl AsRef<[u8]>,
+) -> io::Result<()> {
+    let temp = temp_file(path);
+    let result = match fs::write(&temp, contents).await {
+        Ok(()) => fs::rename(&temp, path).await,
+        Err(err) => Err(err),
+    };
+    if result.is_err() {
+        let _ = fs::remove_file(&temp).await;
+    }
+    result
+}
+
+/// Lock `path` against other processes, waiting until any other process
+/// holding the lock releases it.
+pub(crate) async fn lock(path: &Path) -> CacheLock {
+    loop {
+        if let Some(lock) = CacheLock::try_acquire(path) {
+            return lock;
+        }
+        time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// Run `work`, which may block on locks held by other processes, without
+/// holding up the runtime's worker threads.
+pub(crate) async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+) -> T {
+    task::spawn_blocking(work)
+        .await
+        .unwrap_or_else(|err| panic::resume_unwind(err.into_panic()))
+}
+
+/// Migrate the cache to the current layout (see [`year_dir`]) on a blocking
+/// thread, so that the first path built into it doesn't hold up the runtime.
+///
+/// [`year_dir`]: crate::cache::year_dir
+pub(crate) async fn migrate() {
+    if !is_migrated() {
+        blocking(ensure_migrated).await;
+    }
+}
+
+/// Queue an answer to be submitted later; see [`queue::enqueue`].
+pub(crate) async fn enqueue(year: i32, day: u32, part: u32, answer: &str) {
+    let answer = answer.to_string();
+    blocking(move || queue::enqueue(year, day, part, &answer)).await;
+}
+
+/// Remove a delivered answer from the queue; see [`queue::dequeue`].
+pub(crate) async fn dequeue(queued: &QueuedAnswer) {
+    let queued = queued.clone();
+    blocking(move || queue::dequeue(&queued)).await;
+}
+
+/// Every answer waiting to be submitted; see [`queue::queued_answers`].
+pub(crate) async fn queued_answers() -> Vec<QueuedAnswer> {
+    blocking(queue::queued_answers).await
+}
+
+/// Record a run in the day's cache; see [`timings::record_run`].
+pub(crate) async fn record_run(
+    year: i32,
+    day: u32,
+    part: u32,
+    fetch: Duration,
+    parse: &Stage,
+    solve: &Stage,
+) {
+    let (parse, solve) = (*parse, *solve);
+    blocking(move || timings::record_run(year, day, part, fetch, &parse, &solve)).await;
+}
+
+/// Wait for a slot to send a request to `url`; see [`throttle::reserve`].
+async fn reserve(url: &str, repeatable: bool) {
+    let url = url.to_string();
+    time::sleep(blocking(move || throttle::reserve(&url, repeatable)).await).await;
+}
+
 lazy_static! {
     static ref CLIENT: Client = Client::builder()
         .user_agent(USER_AGENT)
+        .timeout(duration_setting(TIMEOUT_FILE, REQUEST_TIMEOUT))
         .build()
         .expect("Failed to build reqwest client.");
 }
 
-pub(crate) async fn get(url: &str, authenticate: bool) -> Response {
-    if authenticate {
-        CLIENT.get(url).header(header::COOKIE, get_cookie().await)
-    } else {
-        CLIENT.get(url)
+/// The number of seconds to wait for a response before giving up.
+const TIMEOUT_FILE: &str = "request_timeout.txt";
+
+/// Read the body of `resp`, and record the request in the audit log.
+async fn read_reply(
+    method: &str,
+    url: &str,
+    authenticate: bool,
+    api: &str,
+    resp: Response,
+) -> Reply {
+    let reply = Reply {
+        status: resp.status(),
+        body: resp
+            .text()
+            .await
+            .expect("Advent of Code sent back a bad response"),
+    };
+    log_request(method, url, authenticate, Ok(&reply), api).await;
+    reply
+}
+
+/// Record a request in the audit log; see [`audit::log_request`].
+async fn log_request(
+    method: &str,
+    url: &str,
+    authenticate: bool,
+    outcome: Result<&Reply, RequestFailure>,
+    api: &str,
+) {
+    let record = audit::request_record(method, url, authenticate, outcome, api);
+    blocking(move || audit::append_record(&record)).await;
+}
+
+/// Send the request built by `build`, retrying connection failures, timeouts
+/// and server errors with exponential backoff. Once the retries run out, a
+/// server error is returned as-is.
+///
+/// Unless `idempotent` is set, failures where the request may have reached
+/// Advent of Code (timeouts and server errors) are not retried; they're
+/// reported as [`SendError::MaybeSent`] instead, so that the caller can check
+/// what happened.
+///
+/// If Advent of Code can't be connected to, offline mode is switched on.
+async fn send(
+    method: &str,
+    url: &str,
+    authenticate: bool,
+    api: &str,
+    idempotent: bool,
+    build: impl Fn() -> RequestBuilder,
+) -> Result<Reply, SendError> {
+    let mut attempt = 0;
+    loop {
+        let sent = build().send().await;
+        if let Err(err) = &sent {
+            let failure = RequestFailure::of(err);
+            log_request(method, url, authenticate, Err(failure), api).await;
+        }
+        let problem = match sent {
+            Ok(resp) if resp.status().is_server_error() => {
+                let reply = read_reply(method, url, authenticate, api, resp).await;
+                if attempt == MAX_RETRIES {
+                    return Ok(reply);
+                }
+                if !idempotent {
+                    return Err(SendError::MaybeSent);
+                }
+                format!("Advent of Code returned {}.", reply.status)
+            },
+            Ok(resp) => {
+                return Ok(read_reply(method, url, authenticate, api, resp).await);
+            },
+            Err(err) if err.is_connect() || (idempotent && err.is_timeout()) => {
+                if attempt == MAX_RETRIES {
+                    if err.is_connect() {
+                        go_offline();
+                    }
+                    return Err(SendError::NotSent(err));
+                }
+                if err.is_timeout() {
+                    "The request timed out.".into()
+                } else {
+                    "Failed to connect to Advent of Code.".into()
+                }
+            },
+            Err(err) if idempotent => return Err(SendError::NotSent(err)),
+            Err(_) => return Err(SendError::MaybeSent),
+        };
+        wait(
+            format!("{} {}", problem.yellow(), "Retrying in".yellow()),
+            backoff(attempt),
+        )
+        .await;
+        attempt += 1;
+        reserve(url, true).await;
     }
-    .send()
+}
+
+/// Send a GET request to `url`, retrying failures.
+async fn try_get(url: &str, authenticate: bool, api: &str) -> Result<Reply, SendError> {
+    let cookie = if authenticate {
+        Some(get_cookie().await)
+    } else {
+        None
+    };
+    send("GET", url, authenticate, api, true, || {
+        let request = CLIENT.get(url);
+        match &cookie {
+            Some(cookie) => request.header(header::COOKIE, cookie),
+            None => request,
+        }
+    })
     .await
-    .expect("Advent of Code sent back a bad response, or the network is down.")
 }
 
-pub(crate) async fn get_text(url: &str, authenticate: bool) -> String {
-    get(url, authenticate)
-        .await
-        .text()
-        .await
-        .expect("Advent of Code sent back a bad response")
+/// Send a GET request to `url` on behalf of the library function `api`.
+///
+/// Nothing is sent in offline mode, so that callers can fall back to their
+/// cache; offline mode is switched on if Advent of Code can't be reached.
+pub(crate) async fn get(
+    url: &str,
+    authenticate: bool,
+    api: &str,
+) -> Result<Reply, SendError> {
+    if is_offline() {
+        let failure = RequestFailure::Offline;
+        log_request("GET", url, authenticate, Err(failure), api).await;
+        return Err(SendError::Offline);
+    }
+    reserve(url, false).await;
+    let reply = try_get(url, authenticate, api).await?;
+    if reply.status.is_success() {
+        let url = url.to_string();
+        blocking(move || throttle::record_success(&url)).await;
+    }
+    Ok(reply)
 }
 
+/// Send a POST request to `url` on behalf of the library function `api`.
+///
+/// POST requests are never retried if they may have reached Advent of Code, as
+/// they may not be safe to repeat. Nothing is sent in offline mode.
 pub(crate) async fn post(
     url: &str,
     authenticate: bool,
     data: impl serde::Serialize,
-) -> Response {
-    if authenticate {
-        CLIENT.post(url).header(header::COOKIE, get_cookie().await)
-    } else {
-        CLIENT.post(url)
+    api: &str,
+) -> Result<Reply, SendError> {
+    if is_offline() {
+        let failure = RequestFailure::Offline;
+        log_request("POST", url, authenticate, Err(failure), api).await;
+        return Err(SendError::Offline);
     }
-    .form(&data)
-    .send()
+    // answers are deduplicated by the submission cache, and are only resent
+    // when Advent of Code asks us to wait and try again
+    reserve(url, true).await;
+    let cookie = if authenticate {
+        Some(get_cookie().await)
+    } else {
+        None
+    };
+    send("POST", url, authenticate, api, false, || {
+        let request = CLIENT.post(url).form(&data);
+        match &cookie {
+            Some(cookie) => request.header(header::COOKIE, cookie),
+            None => request,
+        }
+    })
     .await
-    .expect("Advent of Code sent back a bad response, or the network is down.")
+}
+
+/// Check the puzzle page to see whether `answer` was accepted for `part`,
+/// after submitting it failed in a way that it may have been received.
+///
+/// Returns [`None`] if the puzzle page can't be fetched either.
+pub(crate) async fn check_answer_accepted(
+    day: u32,
+    part: u32,
+    year: impl Display,
+    answer: &str,
+) -> Option<bool> {
+    let url = base_url(year, day);
+    eprintln!(
+        "{}",
+        "Your answer may not have reached Advent of Code; checking the puzzle page..."
+            .yellow()
+    );
+    // the page has (probably) changed since it was last fetched, so it's fine
+    // to fetch it again immediately
+    reserve(&url, true).await;
+    try_get(&url, true, "submit")
+        .await
+        .ok()
+        .filter(|reply| reply.status.is_success())
+        .map(|reply| answer_accepted(&reply.body, day, part, answer))
 }
 
 pub(crate) async fn load_leaderboard_times(
     day: u32,
     year: i32,
-) -> (Vec<f64>, Vec<f64>) {
-    let day_dir = &*DATA_DIR / year.to_string() / day.to_string();
-    make(&day_dir).await;
+) -> Result<(Vec<f64>, Vec<f64>), SendError> {
+    make(&day_dir(year, day)).await;
 
-    let leaderboards = day_dir / "leaderboards.json";
+    let leaderboards = leaderboards_file(year, day);
     if leaderboards.exists() {
         let data = tokio::fs::read_to_string(&leaderboards)
             .await
             .expect("Failed to read leaderboards file.");
-        serde_json::from_str(&data).expect("Failed to parse leaderboard cache.")
+        Ok(serde_json::from_str(&data).expect("Failed to parse leaderboard cache."))
     } else {
-        let leaderboard_page = get_text(&leaderboard_url(year, day), false).await;
+        let leaderboard_page = get(&leaderboard_url(year, day), false, "leaderboard")
+            .await?
+            .body;
         let soup =
             tl::parse(&leaderboard_page, tl::ParserOptions::new().track_classes())
                 .expect("Parsing the leaderboard page failed.");
@@ -235,7 +535,7 @@ pub(crate) async fn load_leaderboard_times(
         }
         if part_1_times.len() == 100 && part_2_times.len() == 100 {
             // Both leaderboards are full, cache them
-            fs::write(
+            write_atomic(
                 &leaderboards,
                 serde_json::to_string(&(&part_1_times, &part_2_times))
                     .expect("Serialising should never fail"),
@@ -243,12 +543,12 @@ pub(crate) async fn load_leaderboard_times(
             .await
             .expect("Failed to write leaderboard cache. Please check your permissions");
         }
-        (part_1_times, part_2_times)
+        Ok((part_1_times, part_2_times))
     }
 }
 
 pub(crate) async fn practice_result_for(day: u32, year: i32) -> (PathDiv, Vec<f64>) {
-    let practice_data_dir = &*PRACTICE_DATA_DIR / year.to_string() / day.to_string();
+    let practice_data_dir = practice_dir(year, day);
     make(&practice_data_dir).await;
     let now = Utc::now();
     let file = practice_data_dir
@@ -298,29 +598,27 @@ pub(crate) async fn calculate_practice_result(day: u32, part: u32, year: i32) {
         .to_std()
         .expect("Should never be negative")
         .as_secs_f64();
-    let (file, mut data) = practice_result_for(day, year).await;
-    data.push(solve_time);
-    fs::write(
-        file,
-        serde_json::to_string(&data).expect("Serialising results should be infallible"),
-    )
-    .await
-    .expect("Saving practice results failed");
+    make(&practice_dir(year, day)).await;
+    {
+        // another process may record a result for this day while we do
+        let _lock = lock(&practice_dir(year, day)).await;
+        let (file, mut data) = practice_result_for(day, year).await;
+        data.push(solve_time);
+        write_atomic(
+            &file,
+            serde_json::to_string(&data)
+                .expect("Serialising results should be infallible"),
+        )
+        .await
+        .expect("Saving practice results failed");
+    }
     report_practice_result(day, part, year, solve_time).await;
 }
 
-async fn estimate_practice_rank(
-    day: u32,
-    part: u32,
-    year: i32,
+fn estimate_practice_rank(
+    leaderboard: &[f64],
     solve_time: f64,
 ) -> Option<(usize, usize, usize)> {
-    let leaderboard = load_leaderboard_times(day, year).await;
-    let leaderboard = match part {
-        1 => leaderboard.0,
-        2 => leaderboard.1,
-        _ => panic!("part was neither 1 nor 2"),
-    };
     let truncated_solve_time = solve_time.trunc();
     let best_possible_rank =
         leaderboard.partition_point(|&opp_time| opp_time < truncated_solve_time) + 1;
@@ -340,6 +638,23 @@ async fn estimate_practice_rank(
     }
 }
 
+/// Estimate the rank a solve off the global leaderboard would have achieved,
+/// and the percentage of all solvers that rank places it in.
+async fn estimate_practice_percentile(
+    leaderboard: &[f64],
+    day: u32,
+    part: u32,
+    year: i32,
+    solve_time: f64,
+) -> Option<(f64, f64)> {
+    let rank = extrapolate_rank(leaderboard, solve_time)?;
+    let percentile = try_global_stats(year)
+        .await
+        .ok()?
+        .percentile(day, part, rank)?;
+    Some((rank, percentile))
+}
+
 async fn report_practice_result(day: u32, part: u32, year: i32, solve_time: f64) {
     println!(
         "{} {}{}",
@@ -347,14 +662,54 @@ async fn report_practice_result(day: u32, part: u32, year: i32, solve_time: f64)
         format_time(solve_time).blue(),
         '!'.green(),
     );
+    if is_offline() {
+        println!(
+            "{}",
+            "Skipping leaderboard estimation, as raoc is offline.".yellow()
+        );
+        return;
+    }
 
-    let result = estimate_practice_rank(day, part, year, solve_time).await;
-    match result {
-        None => {
+    let leaderboard = match load_leaderboard_times(day, year).await {
+        Ok(leaderboard) => leaderboard,
+        Err(err) => {
             println!(
                 "{}",
-                "You would not have achieved a leaderboard position.".yellow()
+                format!("Skipping leaderboard estimation, as {err}.").yellow()
             );
+            return;
+        },
+    };
+    let leaderboard = match part {
+        1 => leaderboard.0,
+        2 => leaderboard.1,
+        _ => panic!("part was neither 1 nor 2"),
+    };
+    let result = estimate_practice_rank(&leaderboard, solve_time);
+    match result {
+        None => match estimate_practice_percentile(
+            &leaderboard,
+            day,
+            part,
+            year,
+            solve_time,
+        )
+        .await
+        {
+            Some((rank, percentile)) => println!(
+                "{} {} {}{}{}",
+                "You would not have achieved a leaderboard position, but would have \
+                 achieved approximately rank"
+                    .yellow(),
+                format!("{rank:.0}").blue(),
+                "(top ".yellow(),
+                format!("{percentile:.2}%").blue(),
+                " of solvers).".yellow(),
+            ),
+            None => println!(
+                "{}",
+                "You would not have achieved a leaderboard position.".yellow()
+            ),
         },
         Some((_approx, best, worst)) if best == worst => {
             println!(
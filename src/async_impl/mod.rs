@@ -1,4 +1,4 @@
 mod interface;
 mod internal_util;
 pub use interface::*;
-pub use internal_util::{wait, work};
+pub use internal_util::{wait, work, work_with_time_limit};
@@ -0,0 +1,252 @@
+use std::fmt::{self, Display};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use crossterm::style::Stylize;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::CacheLock;
+use crate::data::DATA_DIR;
+use crate::internal_util::Reply;
+
+/// The request log, as JSON lines.
+const LOG_FILE: &str = "requests.log";
+/// The size at which the request log is rotated.
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+/// The number of rotated logs to keep, as `requests.log.1` (the newest) to
+/// `requests.log.3` (the oldest).
+const KEPT_LOGS: u32 = 3;
+
+/// Why a request got no response from Advent of Code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestFailure {
+    /// Advent of Code couldn't be connected to.
+    Connect,
+    /// No response came back in time.
+    Timeout,
+    /// The request wasn't sent, as `raoc` is offline.
+    Offline,
+    /// The request failed some other way, such as the connection dropping.
+    Other,
+}
+
+impl RequestFailure {
+    /// Classify a failed request.
+    pub(crate) fn of(err: &reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::Timeout
+        } else if err.is_connect() {
+            Self::Connect
+        } else {
+            Self::Other
+        }
+    }
+}
+
+impl Display for RequestFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Connect => "connection failed",
+            Self::Timeout => "timed out",
+            Self::Offline => "offline",
+            Self::Other => "failed",
+        })
+    }
+}
+
+/// A request made to Advent of Code.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestRecord {
+    /// When the request was made, in seconds since the Unix epoch.
+    pub timestamp: i64,
+    /// The HTTP method, such as `GET`.
+    pub method: String,
+    /// The path requested, such as `/2023/day/7/input`.
+    pub path: String,
+    /// Whether the session cookie was sent. The cookie itself is never
+    /// recorded.
+    pub authenticated: bool,
+    /// The HTTP status code of the response, or `None` if no response came
+    /// back.
+    pub status: Option<u16>,
+    /// The size of the response body in bytes.
+    pub bytes: usize,
+    /// Why no response came back, if one didn't.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<RequestFailure>,
+    /// The library function which made the request, such as `fetch`.
+    pub api: String,
+}
+
+impl RequestRecord {
+    /// When the request was made.
+    #[must_use]
+    pub fn time(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.timestamp, 0).unwrap_or_default()
+    }
+
+    /// The year and day of the puzzle the request was for, if any.
+    #[must_use]
+    pub fn puzzle(&self) -> (Option<i32>, Option<u32>) {
+        let mut segments = self.path.split('/').skip(1);
+        let year = segments.next().and_then(|year| year.parse().ok());
+        let day = segments
+            .skip_while(|&segment| segment != "day")
+            .nth(1)
+            .and_then(|day| day.parse().ok());
+        (year, day)
+    }
+}
+
+impl Display for RequestRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = match self.status {
+            Some(status) if (200..300).contains(&status) => {
+                format!("{} {}B", status.to_string().green(), self.bytes)
+            },
+            Some(status) => format!("{} {}B", status.to_string().red(), self.bytes),
+            None => self
+                .failure
+                .map_or_else(|| "no response".into(), |failure| failure.to_string())
+                .red()
+                .to_string(),
+        };
+        write!(
+            f,
+            "{} {:<4} {} {} ({})",
+            self.time().format("%Y-%m-%d %H:%M:%S"),
+            self.method,
+            self.path,
+            outcome,
+            self.api,
+        )
+    }
+}
+
+/// Move the log to `requests.log.1`, shifting older logs along and dropping
+/// the oldest.
+fn rotate() {
+    let _ = fs::remove_file(&*DATA_DIR / format!("{LOG_FILE}.{KEPT_LOGS}"));
+    for generation in (1..KEPT_LOGS).rev() {
+        let _ = fs::rename(
+            &*DATA_DIR / format!("{LOG_FILE}.{generation}"),
+            &*DATA_DIR / format!("{LOG_FILE}.{}", generation + 1),
+        );
+    }
+    let _ = fs::rename(&*DATA_DIR / LOG_FILE, &*DATA_DIR / format!("{LOG_FILE}.1"));
+}
+
+/// Append a request to the log, along with the reply or why there wasn't one.
+/// Failing to do so only produces a warning.
+pub(crate) fn log_request(
+    method: &str,
+    url: &str,
+    authenticated: bool,
+    outcome: Result<&Reply, RequestFailure>,
+    api: &str,
+) {
+    append_record(&request_record(method, url, authenticated, outcome, api));
+}
+
+/// Describe a request, for the log.
+pub(crate) fn request_record(
+    method: &str,
+    url: &str,
+    authenticated: bool,
+    outcome: Result<&Reply, RequestFailure>,
+    api: &str,
+) -> RequestRecord {
+    RequestRecord {
+        timestamp: Utc::now().timestamp(),
+        method: method.into(),
+        path: reqwest::Url::parse(url)
+            .map_or_else(|_| url.into(), |url| url.path().into()),
+        authenticated,
+        status: outcome.ok().map(|reply| reply.status.as_u16()),
+        bytes: outcome.map_or(0, |reply| reply.body.len()),
+        failure: outcome.err(),
+        api: api.into(),
+    }
+}
+
+/// Append a record to the log. Failing to do so only produces a warning.
+pub(crate) fn append_record(record: &RequestRecord) {
+    let log_file = &*DATA_DIR / LOG_FILE;
+    let _lock = CacheLock::acquire(&log_file);
+    if fs::metadata(&log_file).is_ok_and(|metadata| metadata.len() >= MAX_LOG_SIZE) {
+        rotate();
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_file)
+        .and_then(|mut file| {
+            writeln!(
+                file,
+                "{}",
+                serde_json::to_string(record).expect("Serialising should never fail")
+            )
+        })
+        .unwrap_or_else(|_| {
+            eprintln!(
+                "{}",
+                "Warning: Failed to write to the request log. Please check your \
+                 permissions."
+                    .red()
+            );
+        });
+}
+
+/// Every request in the log (including rotated logs), oldest first.
+#[must_use]
+pub fn request_log() -> Vec<RequestRecord> {
+    (1..=KEPT_LOGS)
+        .rev()
+        .map(|generation| &*DATA_DIR / format!("{LOG_FILE}.{generation}"))
+        .chain([&*DATA_DIR / LOG_FILE])
+        .filter_map(|file| fs::read_to_string(file).ok())
+        .flat_map(|log| {
+            log.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Criteria for selecting requests from the log. Unset criteria match every
+/// request.
+#[derive(Debug, Clone, Default)]
+pub struct RequestFilter {
+    /// Only requests made on this date (in UTC).
+    pub date: Option<NaiveDate>,
+    /// Only requests for puzzles from this year.
+    pub year: Option<i32>,
+    /// Only requests for this day's puzzle.
+    pub day: Option<u32>,
+    /// Only requests made by this library function, such as `submit`.
+    pub api: Option<String>,
+}
+
+impl RequestFilter {
+    /// Whether `record` meets every criterion.
+    #[must_use]
+    pub fn matches(&self, record: &RequestRecord) -> bool {
+        let (year, day) = record.puzzle();
+        self.date
+            .is_none_or(|date| record.time().date_naive() == date)
+            && self.year.is_none_or(|wanted| year == Some(wanted))
+            && self.day.is_none_or(|wanted| day == Some(wanted))
+            && self.api.as_ref().is_none_or(|api| &record.api == api)
+    }
+}
+
+/// The requests in the log which match `filter`, oldest first.
+#[must_use]
+pub fn query_requests(filter: &RequestFilter) -> Vec<RequestRecord> {
+    request_log()
+        .into_iter()
+        .filter(|record| filter.matches(record))
+        .collect()
+}
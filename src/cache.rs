@@ -0,0 +1,555 @@
+use std::fmt::{self, Display};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Once;
+use std::{io, process};
+
+use crossterm::style::Stylize;
+use pathdiv::PathDiv;
+
+use crate::data::{DATA_DIR, PRACTICE_DATA_DIR};
+
+/// The current version of the cache layout.
+///
+/// Version 0 is any data directory written before the layout was versioned,
+/// in which `raoc` recorded submissions in `submissions.txt` rather than
+/// `submissions.json` (as `aoc_helper` does).
+pub(crate) const CACHE_VERSION: u32 = 1;
+
+const VERSION_FILE: &str = "raoc_cache_version.txt";
+pub(crate) const SUBMISSIONS: &str = "submissions.json";
+pub(crate) const LEGACY_SUBMISSIONS: &str = "submissions.txt";
+/// Where a legacy submission history which couldn't be migrated is moved to.
+pub(crate) const UNPARSED_LEGACY_SUBMISSIONS: &str = "submissions.txt.unparsed";
+pub(crate) const LEADERBOARDS: &str = "leaderboards.json";
+pub(crate) const TESTS: &str = "tests.json";
+pub(crate) const TIMINGS: &str = "timings.json";
+pub(crate) const PERSONAL_STATS: &str = "personal_stats.json";
+pub(crate) const GLOBAL_STATS: &str = "stats.json";
+pub(crate) const CALENDAR: &str = "calendar.json";
+pub(crate) const PRIVATE_LEADERBOARDS: &str = "private_leaderboards";
+
+static MIGRATION: Once = Once::new();
+/// How many temporary files this process has named, so that each is unique.
+static TEMP_FILES: AtomicU64 = AtomicU64::new(0);
+
+/// The directory holding everything cached for `year`.
+///
+/// Every path into the data directory should be built from this, so that the
+/// cache is migrated to the current layout before it's used.
+pub(crate) fn year_dir(year: i32) -> PathDiv {
+    ensure_migrated();
+    &*DATA_DIR / year.to_string()
+}
+
+/// Migrate the cache to the current layout, if that hasn't been done yet in
+/// this process.
+pub(crate) fn ensure_migrated() {
+    MIGRATION.call_once(|| {
+        #[cfg(feature = "web")]
+        for change in migrate_cache() {
+            eprintln!("{} {change}", "Migrated cache:".yellow());
+        }
+    });
+}
+
+/// Whether [`ensure_migrated`] has already run in this process.
+#[cfg(feature = "async")]
+pub(crate) fn is_migrated() -> bool {
+    MIGRATION.is_completed()
+}
+
+/// The directory holding everything cached for `day` of `year`, other than
+/// the input.
+pub(crate) fn day_dir(year: i32, day: u32) -> PathDiv {
+    year_dir(year) / day.to_string()
+}
+
+pub(crate) fn input_file(year: i32, day: u32) -> PathDiv {
+    year_dir(year) / format!("{day}.in")
+}
+
+/// The metadata recorded when `input_file` was fetched.
+pub(crate) fn input_metadata_file(input_file: &Path) -> PathBuf {
+    input_file.with_extension("in.meta")
+}
+
+pub(crate) fn solution_file(year: i32, day: u32, part: u32) -> PathDiv {
+    day_dir(year, day) / format!("{part}.solution")
+}
+
+pub(crate) fn submissions_file(year: i32, day: u32) -> PathDiv {
+    day_dir(year, day) / SUBMISSIONS
+}
+
+pub(crate) fn timings_file(year: i32, day: u32) -> PathDiv {
+    day_dir(year, day) / TIMINGS
+}
+
+pub(crate) fn leaderboards_file(year: i32, day: u32) -> PathDiv {
+    day_dir(year, day) / LEADERBOARDS
+}
+
+pub(crate) fn private_leaderboard_file(year: i32, id: u64) -> PathDiv {
+    year_dir(year) / PRIVATE_LEADERBOARDS / format!("{id}.json")
+}
+
+pub(crate) fn practice_dir(year: i32, day: u32) -> PathDiv {
+    &*PRACTICE_DATA_DIR / year.to_string() / day.to_string()
+}
+
+/// The numbered subdirectories of `dir`, such as years or days.
+pub(crate) fn numbered_dirs(dir: &PathDiv) -> Vec<(i64, PathDiv)> {
+    let mut dirs = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| {
+                    let number = entry.file_name().to_str()?.parse().ok()?;
+                    Some((number, dir / entry.file_name()))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    dirs.sort_by_key(|&(number, _)| number);
+    dirs
+}
+
+/// The path `path` with `suffix` appended to its (hidden) file name, in the
+/// same directory.
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .expect("Cache paths always have a file name")
+        .to_string_lossy();
+    path.with_file_name(format!(".{name}.{suffix}"))
+}
+
+/// The temporary file that `path` is written to before being renamed into
+/// place.
+///
+/// This is unique to the process and the call, so concurrent writers (even
+/// threads of the same process) never clobber each other's partial writes.
+pub(crate) fn temp_file(path: &Path) -> PathBuf {
+    let count = TEMP_FILES.fetch_add(1, Ordering::Relaxed);
+    sidecar(path, &format!("{}.{count}.tmp", process::id()))
+}
+
+/// Write `contents` to `path` atomically: readers (including other processes)
+/// see either the old contents or the new contents, never a truncated file.
+pub(crate) fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let temp = temp_file(path);
+    let result = fs::write(&temp, contents).and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// An advisory lock on a cache file, shared between every process using
+/// `raoc` (or anything else which respects the lock). It is released when
+/// dropped.
+///
+/// The lock is taken on a separate, hidden lock file, as the cache file itself
+/// is replaced on every write.
+#[must_use = "The lock is released as soon as it is dropped"]
+pub(crate) struct CacheLock {
+    _file: File,
+}
+
+impl CacheLock {
+    fn open(path: &Path) -> File {
+        File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(sidecar(path, "lock"))
+            .expect("Failed to open cache lock file. Please check your permissions.")
+    }
+
+    /// Lock `path`, blocking until any other process holding the lock
+    /// releases it.
+    pub(crate) fn acquire(path: &Path) -> Self {
+        let file = Self::open(path);
+        file.lock().expect("Failed to lock cache file.");
+        Self { _file: file }
+    }
+
+    /// Lock `path`, if no other process holds the lock.
+    #[cfg(feature = "async")]
+    pub(crate) fn try_acquire(path: &Path) -> Option<Self> {
+        let file = Self::open(path);
+        match file.try_lock() {
+            Ok(()) => Some(Self { _file: file }),
+            Err(fs::TryLockError::WouldBlock) => None,
+            Err(fs::TryLockError::Error(_)) => panic!("Failed to lock cache file."),
+        }
+    }
+}
+
+/// A problem found in the data directory.
+#[derive(Debug, Clone)]
+pub struct CacheIssue {
+    /// The file with the problem.
+    pub path: PathBuf,
+    /// A description of the problem.
+    pub problem: String,
+}
+
+impl Display for CacheIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.problem)
+    }
+}
+
+#[cfg(feature = "web")]
+mod versioned {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::Path;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use pathdiv::PathDiv;
+    use serde::{Deserialize, Serialize};
+
+    use super::{
+        input_file,
+        input_metadata_file,
+        numbered_dirs,
+        solution_file,
+        write_atomic,
+        CacheIssue,
+        CacheLock,
+        CACHE_VERSION,
+        LEADERBOARDS,
+        LEGACY_SUBMISSIONS,
+        SUBMISSIONS,
+        TESTS,
+        UNPARSED_LEGACY_SUBMISSIONS,
+        VERSION_FILE,
+    };
+    use crate::data::DATA_DIR;
+    use crate::internal_util::{check_input, Submissions, TestInfo};
+
+    fn read_version() -> u32 {
+        fs::read_to_string(&*DATA_DIR / VERSION_FILE)
+            .ok()
+            .and_then(|version| version.trim().parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Parse a submission history, if it exists and is valid.
+    pub(crate) fn read_submissions(file: &PathDiv) -> Option<Submissions> {
+        serde_json::from_str(&fs::read_to_string(file).ok()?).ok()
+    }
+
+    /// The 64-bit FNV-1a hash of `data`.
+    fn fnv1a(data: &[u8]) -> u64 {
+        data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    /// What was fetched for a puzzle input, so that the cached copy can be
+    /// checked for corruption.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub(crate) struct InputMetadata {
+        /// When the input was fetched, in seconds since the Unix epoch.
+        fetched_at: u64,
+        /// The length of the input in bytes.
+        size: usize,
+        /// The FNV-1a hash of the input, in hex.
+        hash: String,
+    }
+
+    impl InputMetadata {
+        pub(crate) fn new(input: &str, fetched_at: SystemTime) -> Self {
+            Self {
+                fetched_at: fetched_at
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |time| time.as_secs()),
+                size: input.len(),
+                hash: format!("{:016x}", fnv1a(input.as_bytes())),
+            }
+        }
+
+        pub(crate) fn to_json(&self) -> String {
+            serde_json::to_string(self).expect("Serialising should never fail")
+        }
+    }
+
+    /// Check a cached input against the metadata recorded when it was fetched,
+    /// and that it actually looks like a puzzle input.
+    ///
+    /// Inputs cached before metadata was recorded are only checked for the
+    /// latter.
+    pub(crate) fn verify_input(input_file: &Path, input: &str) -> Result<(), String> {
+        check_input(input).map_err(|problem| problem.to_string())?;
+        let metadata_file = input_metadata_file(input_file);
+        if !metadata_file.exists() {
+            return Ok(());
+        }
+        let metadata: InputMetadata = fs::read_to_string(&metadata_file)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .ok_or("the input metadata could not be parsed")?;
+        let actual = InputMetadata::new(input, SystemTime::now());
+        if actual.size != metadata.size {
+            Err(format!(
+                "the input is {} bytes, but {} bytes were fetched",
+                actual.size, metadata.size
+            ))
+        } else if actual.hash != metadata.hash {
+            Err(
+                "the input does not match the checksum recorded when it was fetched"
+                    .into(),
+            )
+        } else {
+            Ok(())
+        }
+    }
+
+    fn merge_responses(
+        into: &mut HashMap<String, String>,
+        from: HashMap<String, String>,
+    ) {
+        for (answer, response) in from {
+            into.entry(answer).or_insert(response);
+        }
+    }
+
+    /// Bring the data directory up to date with the current cache layout.
+    ///
+    /// This is done automatically the first time any cached file is accessed,
+    /// so it should rarely be necessary to call it directly. Returns a
+    /// description of each change made.
+    #[must_use]
+    pub fn migrate_cache() -> Vec<String> {
+        let version = read_version();
+        let mut changes = Vec::new();
+        if version > CACHE_VERSION {
+            changes.push(format!(
+                "The data directory uses cache version {version}, but this version of \
+                 raoc only understands version {CACHE_VERSION}; nothing was changed"
+            ));
+            return changes;
+        }
+        if version == CACHE_VERSION {
+            return changes;
+        }
+
+        // 0 -> 1: merge raoc's submissions.txt into aoc_helper's
+        // submissions.json
+        let mut migrated = true;
+        for (_, year_dir) in numbered_dirs(&DATA_DIR) {
+            for (_, day_dir) in numbered_dirs(&year_dir) {
+                let legacy_file = &day_dir / LEGACY_SUBMISSIONS;
+                if !legacy_file.exists() {
+                    continue;
+                }
+                let Some(legacy) = read_submissions(&legacy_file) else {
+                    // set it aside, so that it's only reported once
+                    let unparsed_file = &day_dir / UNPARSED_LEGACY_SUBMISSIONS;
+                    if fs::rename(&legacy_file, &unparsed_file).is_ok() {
+                        changes.push(format!(
+                            "{} could not be parsed, and was renamed to {}",
+                            legacy_file.display(),
+                            unparsed_file.display()
+                        ));
+                    } else {
+                        changes.push(format!(
+                            "{} could not be parsed, and was left in place",
+                            legacy_file.display()
+                        ));
+                        migrated = false;
+                    }
+                    continue;
+                };
+                let file = &day_dir / SUBMISSIONS;
+                let _lock = CacheLock::acquire(&file);
+                let mut submissions = if file.exists() {
+                    if let Some(submissions) = read_submissions(&file) {
+                        submissions
+                    } else {
+                        changes.push(format!(
+                            "{} could not be parsed, so {} was left in place",
+                            file.display(),
+                            legacy_file.display()
+                        ));
+                        migrated = false;
+                        continue;
+                    }
+                } else {
+                    Submissions {
+                        part_1: HashMap::new(),
+                        part_2: HashMap::new(),
+                    }
+                };
+                merge_responses(&mut submissions.part_1, legacy.part_1);
+                merge_responses(&mut submissions.part_2, legacy.part_2);
+                if write_atomic(
+                    &file,
+                    serde_json::to_string(&submissions)
+                        .expect("Serialising should never fail"),
+                )
+                .and_then(|()| fs::remove_file(&legacy_file))
+                .is_ok()
+                {
+                    changes.push(format!(
+                        "merged {} into {}",
+                        legacy_file.display(),
+                        file.display()
+                    ));
+                } else {
+                    changes.push(format!(
+                        "failed to merge {} into {}; please check your permissions",
+                        legacy_file.display(),
+                        file.display()
+                    ));
+                    migrated = false;
+                }
+            }
+        }
+
+        if migrated {
+            write_atomic(&(&*DATA_DIR / VERSION_FILE), CACHE_VERSION.to_string())
+                .unwrap_or_else(|_| {
+                    changes.push(
+                        "failed to record the cache version; please check your \
+                         permissions"
+                            .into(),
+                    );
+                });
+        }
+        changes
+    }
+
+    /// Check the data directory for inconsistencies, such as accepted answers
+    /// with no record of being submitted, or cache files that can't be parsed.
+    ///
+    /// Nothing is modified; each problem found is returned.
+    #[must_use]
+    pub fn validate_cache() -> Vec<CacheIssue> {
+        let mut issues = Vec::new();
+        let mut issue = |path: &PathDiv, problem: &str| {
+            issues.push(CacheIssue {
+                path: path.to_path_buf(),
+                problem: problem.into(),
+            });
+        };
+
+        let version = read_version();
+        if version != CACHE_VERSION {
+            issue(
+                &(&*DATA_DIR / VERSION_FILE),
+                &format!(
+                    "cache version is {version}, but the current version is \
+                     {CACHE_VERSION}"
+                ),
+            );
+        }
+
+        for (year, year_dir) in numbered_dirs(&DATA_DIR) {
+            let Ok(year) = i32::try_from(year) else {
+                continue;
+            };
+            for day in 1..=25 {
+                let input_file = input_file(year, day);
+                if let Ok(input) = fs::read_to_string(&input_file) {
+                    if let Err(problem) = verify_input(&input_file, &input) {
+                        issue(&input_file, &problem);
+                    }
+                }
+            }
+
+            for (day, day_dir) in numbered_dirs(&year_dir) {
+                let Ok(day) = u32::try_from(day) else {
+                    continue;
+                };
+                let legacy_file = &day_dir / LEGACY_SUBMISSIONS;
+                if legacy_file.exists() {
+                    issue(&legacy_file, "legacy submission history was not migrated");
+                }
+                let unparsed_file = &day_dir / UNPARSED_LEGACY_SUBMISSIONS;
+                if unparsed_file.exists() {
+                    issue(
+                        &unparsed_file,
+                        "legacy submission history could not be parsed, and was set \
+                         aside",
+                    );
+                }
+
+                let submissions_file = &day_dir / SUBMISSIONS;
+                let submissions = read_submissions(&submissions_file);
+                if submissions_file.exists() && submissions.is_none() {
+                    issue(&submissions_file, "submission history could not be parsed");
+                }
+
+                for part in 1..=2 {
+                    let solution_file = solution_file(year, day, part);
+                    let responses =
+                        submissions.as_ref().map(|submissions| match part {
+                            1 => &submissions.part_1,
+                            _ => &submissions.part_2,
+                        });
+                    if let Ok(solution) = fs::read_to_string(&solution_file) {
+                        match responses.and_then(|responses| responses.get(&solution)) {
+                            None if day == 25 && part == 2 => {},
+                            None => issue(
+                                &solution_file,
+                                "accepted answer has no recorded submission",
+                            ),
+                            Some(response) if !response.starts_with("That's the") => {
+                                issue(
+                                    &solution_file,
+                                    "accepted answer was recorded as rejected",
+                                );
+                            },
+                            Some(_) => {},
+                        }
+                    } else if responses.is_some_and(|responses| {
+                        responses
+                            .values()
+                            .any(|response| response.starts_with("That's the"))
+                    }) {
+                        issue(
+                            &submissions_file,
+                            &format!(
+                                "part {part} was accepted, but has no cached answer"
+                            ),
+                        );
+                    }
+                }
+
+                let leaderboards_file = &day_dir / LEADERBOARDS;
+                if leaderboards_file.exists()
+                    && fs::read_to_string(&leaderboards_file)
+                        .ok()
+                        .and_then(|data| {
+                            serde_json::from_str::<(Vec<f64>, Vec<f64>)>(&data).ok()
+                        })
+                        .is_none()
+                {
+                    issue(&leaderboards_file, "leaderboard cache could not be parsed");
+                }
+
+                let tests_file = &day_dir / TESTS;
+                if tests_file.exists()
+                    && fs::read_to_string(&tests_file)
+                        .ok()
+                        .and_then(|data| serde_json::from_str::<TestInfo>(&data).ok())
+                        .is_none()
+                {
+                    issue(&tests_file, "sample test cache could not be parsed");
+                }
+            }
+        }
+        issues
+    }
+}
+#[cfg(feature = "web")]
+pub use versioned::{migrate_cache, validate_cache};
+#[cfg(feature = "web")]
+pub(crate) use versioned::{read_submissions, verify_input, InputMetadata};
@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+use crossterm::style::{style, Stylize};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{day_dir, input_file, numbered_dirs, solution_file};
+use crate::data::{CALENDAR_DAY, DATA_DIR, GOLD, SILVER};
+
+/// How many stars have been collected for a day.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum StarState {
+    /// The day has unlocked, but neither part has been solved.
+    Unsolved,
+    /// Only part 1 has been solved.
+    Silver,
+    /// Both parts have been solved.
+    Gold,
+}
+
+impl StarState {
+    /// The number of stars collected.
+    #[must_use]
+    pub fn stars(self) -> u32 {
+        match self {
+            Self::Unsolved => 0,
+            Self::Silver => 1,
+            Self::Gold => 2,
+        }
+    }
+}
+
+/// The star state of every unlocked day of a year.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Calendar {
+    /// The year this calendar is for.
+    pub year: i32,
+    /// The star state of each unlocked day, keyed by day.
+    pub days: BTreeMap<u32, StarState>,
+}
+
+impl Calendar {
+    /// Parse the calendar from the year's main page.
+    pub(crate) fn parse(year: i32, body: &str) -> Self {
+        let days = CALENDAR_DAY
+            .captures_iter(body)
+            .filter_map(|captures| {
+                let day = captures
+                    .get(1)
+                    .expect("CALENDAR_DAY regex has two captures")
+                    .as_str()
+                    .parse()
+                    .ok()?;
+                let state = match captures.get(2).map(|state| state.as_str()) {
+                    Some("verycomplete") => StarState::Gold,
+                    Some(_) => StarState::Silver,
+                    None => StarState::Unsolved,
+                };
+                Some((day, state))
+            })
+            .collect();
+        Self { year, days }
+    }
+
+    /// Infer the calendar from the solutions cached in the data directory.
+    ///
+    /// Days without any cached solution are assumed to be unsolved; days that
+    /// haven't been fetched or submitted to are not included.
+    #[must_use]
+    pub fn from_cache(year: i32) -> Self {
+        let mut days = (1..=25)
+            .filter(|&day| {
+                input_file(year, day).exists() || day_dir(year, day).exists()
+            })
+            .map(|day| {
+                let state = match (
+                    solution_file(year, day, 1).exists(),
+                    solution_file(year, day, 2).exists(),
+                ) {
+                    (true, true) => StarState::Gold,
+                    (true, false) => StarState::Silver,
+                    (false, _) => StarState::Unsolved,
+                };
+                (day, state)
+            })
+            .collect::<BTreeMap<_, _>>();
+        // Day 25 part 2 is awarded for collecting every other star, and has no
+        // solution to cache
+        if days.get(&25) == Some(&StarState::Silver)
+            && (1..25).all(|day| days.get(&day) == Some(&StarState::Gold))
+        {
+            days.insert(25, StarState::Gold);
+        }
+        Self { year, days }
+    }
+
+    /// Combine two calendars for the same year, keeping the most stars seen
+    /// for each day.
+    #[must_use]
+    pub fn merge(mut self, other: &Self) -> Self {
+        for (&day, &state) in &other.days {
+            let entry = self.days.entry(day).or_insert(state);
+            *entry = (*entry).max(state);
+        }
+        self
+    }
+
+    /// The total number of stars collected this year.
+    #[must_use]
+    pub fn stars(&self) -> u32 {
+        self.days.values().map(|state| state.stars()).sum()
+    }
+
+    /// The days which have unlocked but are missing at least one star.
+    #[must_use]
+    pub fn incomplete_days(&self) -> Vec<u32> {
+        self.days
+            .iter()
+            .filter(|&(_, &state)| state != StarState::Gold)
+            .map(|(&day, _)| day)
+            .collect()
+    }
+}
+
+impl Display for Calendar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {}",
+            format!("Advent of Code {}:", self.year).yellow(),
+            format!("{} stars", self.stars()).with(GOLD),
+        )?;
+        for (day, state) in &self.days {
+            let stars = match state {
+                StarState::Gold => "**".with(GOLD),
+                StarState::Silver => "* ".with(SILVER),
+                StarState::Unsolved => "  ".reset(),
+            };
+            writeln!(f, "{day:>2} {stars}")?;
+        }
+        let incomplete = self.incomplete_days();
+        if !incomplete.is_empty() {
+            writeln!(
+                f,
+                "{} {}",
+                "Incomplete days:".yellow(),
+                style(
+                    incomplete
+                        .iter()
+                        .map(u32::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+                .blue(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Count the stars collected for every year with data in the data directory,
+/// using only cached solutions.
+#[must_use]
+pub fn cached_star_totals() -> BTreeMap<i32, u32> {
+    numbered_dirs(&DATA_DIR)
+        .into_iter()
+        .filter_map(|(year, _)| i32::try_from(year).ok())
+        .filter(|&year| year >= 2015)
+        .map(|year| (year, Calendar::from_cache(year).stars()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Calendar, StarState};
+
+    /// The calendar on an event's main page: days 1 and 2 solved, day 3 half
+    /// solved, day 4 unsolved, and day 5 still locked.
+    const CALENDAR_PAGE: &str = r#"<main><pre class="calendar">
+<a aria-label="Day 1, two stars" href="/2023/day/1" class="calendar-day1 calendar-verycomplete"><span class="calendar-day"> 1</span> <span class="calendar-mark-complete">*</span><span class="calendar-mark-verycomplete">*</span></a>
+<a aria-label="Day 2, two stars" href="/2023/day/2" class="calendar-day2 calendar-verycomplete"><span class="calendar-day"> 2</span> <span class="calendar-mark-complete">*</span><span class="calendar-mark-verycomplete">*</span></a>
+<a aria-label="Day 3, one star" href="/2023/day/3" class="calendar-day3 calendar-complete"><span class="calendar-day"> 3</span> <span class="calendar-mark-complete">*</span><span class="calendar-mark-verycomplete">*</span></a>
+<a aria-label="Day 4" href="/2023/day/4" class="calendar-day4"><span class="calendar-day"> 4</span> <span class="calendar-mark-complete">*</span><span class="calendar-mark-verycomplete">*</span></a>
+<span aria-hidden="true" class="calendar-day5"><span class="calendar-day"> 5</span></span>
+</pre></main>"#;
+
+    #[test]
+    fn calendar_is_parsed() {
+        let calendar = Calendar::parse(2023, CALENDAR_PAGE);
+        assert_eq!(calendar.year, 2023);
+        assert_eq!(
+            calendar.days.into_iter().collect::<Vec<_>>(),
+            [
+                (1, StarState::Gold),
+                (2, StarState::Gold),
+                (3, StarState::Silver),
+                (4, StarState::Unsolved),
+            ]
+        );
+    }
+
+    #[test]
+    fn calendar_counts_stars() {
+        let calendar = Calendar::parse(2023, CALENDAR_PAGE);
+        assert_eq!(calendar.stars(), 5);
+        assert_eq!(calendar.incomplete_days(), [3, 4]);
+    }
+
+    #[test]
+    fn logged_out_calendar_has_no_stars() {
+        // without a session, every unlocked day is shown unsolved
+        let page = CALENDAR_PAGE
+            .replace(" calendar-verycomplete", "")
+            .replace(" calendar-complete", "");
+        let calendar = Calendar::parse(2023, &page);
+        assert_eq!(calendar.days.len(), 4);
+        assert_eq!(calendar.stars(), 0);
+    }
+
+    #[test]
+    fn calendar_can_be_empty() {
+        let calendar = Calendar::parse(2023, "<main></main>");
+        assert!(calendar.days.is_empty());
+        assert_eq!(calendar.stars(), 0);
+    }
+
+    #[test]
+    fn merged_calendars_keep_the_most_stars() {
+        let parsed = Calendar::parse(2023, CALENDAR_PAGE);
+        let mut cached = Calendar::parse(2023, "");
+        cached.days.insert(3, StarState::Gold);
+        cached.days.insert(1, StarState::Silver);
+        let merged = parsed.merge(&cached);
+        assert_eq!(merged.days[&1], StarState::Gold);
+        assert_eq!(merged.days[&3], StarState::Gold);
+        assert_eq!(merged.stars(), 6);
+    }
+}
@@ -0,0 +1,257 @@
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{fs, io};
+
+use crossterm::style::{style, Stylize};
+
+use crate::cache::{
+    input_file,
+    input_metadata_file,
+    numbered_dirs,
+    solution_file,
+    CALENDAR,
+    GLOBAL_STATS,
+    LEADERBOARDS,
+    LEGACY_SUBMISSIONS,
+    PERSONAL_STATS,
+    PRIVATE_LEADERBOARDS,
+    SUBMISSIONS,
+    TESTS,
+    TIMINGS,
+    UNPARSED_LEGACY_SUBMISSIONS,
+};
+use crate::data::{DATA_DIR, PRACTICE_DATA_DIR};
+
+/// A category of data stored in the data directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CacheScope {
+    /// Cached puzzle inputs.
+    Inputs,
+    /// The responses to every answer submitted.
+    Submissions,
+    /// Accepted answers.
+    Solutions,
+    /// Cached global leaderboards, private leaderboards, statistics, and
+    /// calendars.
+    Leaderboards,
+    /// Results recorded in practice mode.
+    Practice,
+    /// Cached sample inputs and answers.
+    SampleTests,
+    /// How long each run of a solution took.
+    Timings,
+}
+
+impl CacheScope {
+    /// Every scope.
+    pub const ALL: [Self; 7] = [
+        Self::Inputs,
+        Self::Submissions,
+        Self::Solutions,
+        Self::Leaderboards,
+        Self::Practice,
+        Self::SampleTests,
+        Self::Timings,
+    ];
+
+    /// Whether data in this scope can be downloaded again after being deleted.
+    #[must_use]
+    pub fn is_recoverable(self) -> bool {
+        matches!(self, Self::Inputs | Self::Leaderboards | Self::SampleTests)
+    }
+
+    /// List the files in this scope, optionally restricted to a single year
+    /// and/or day.
+    ///
+    /// Year-wide files, such as private leaderboards, are not included when a
+    /// day is given.
+    #[must_use]
+    pub fn files(self, year: Option<i32>, day: Option<u32>) -> Vec<PathBuf> {
+        let root = match self {
+            Self::Practice => &*PRACTICE_DATA_DIR,
+            _ => &*DATA_DIR,
+        };
+        let mut files = Vec::new();
+        for (number, year_dir) in numbered_dirs(root) {
+            let Ok(this_year) = i32::try_from(number) else {
+                continue;
+            };
+            if year.is_some_and(|only| only != this_year) {
+                continue;
+            }
+            if day.is_none() && self == Self::Leaderboards {
+                files.extend(
+                    [PERSONAL_STATS, GLOBAL_STATS, CALENDAR]
+                        .map(|file| year_dir.join(file)),
+                );
+                files.extend(entries(&year_dir.join(PRIVATE_LEADERBOARDS)));
+            }
+            if self == Self::Inputs {
+                // inputs and their metadata
+                for day in day.map_or(1..=25, |day| day..=day) {
+                    let input_file = input_file(this_year, day);
+                    files.push(input_file.to_path_buf());
+                    files.push(input_metadata_file(&input_file));
+                }
+                continue;
+            }
+            for (number, day_dir) in numbered_dirs(&year_dir) {
+                let Ok(this_day) = u32::try_from(number) else {
+                    continue;
+                };
+                if day.is_some_and(|only| only != this_day) {
+                    continue;
+                }
+                match self {
+                    Self::Submissions => files.extend(
+                        [LEGACY_SUBMISSIONS, UNPARSED_LEGACY_SUBMISSIONS, SUBMISSIONS]
+                            .map(|file| day_dir.join(file)),
+                    ),
+                    Self::Solutions => files.extend([1, 2].map(|part| {
+                        solution_file(this_year, this_day, part).to_path_buf()
+                    })),
+                    Self::Leaderboards => files.push(day_dir.join(LEADERBOARDS)),
+                    Self::Practice => files.extend(entries(&day_dir)),
+                    Self::SampleTests => files.push(day_dir.join(TESTS)),
+                    Self::Timings => files.push(day_dir.join(TIMINGS)),
+                    Self::Inputs => unreachable!("Inputs are handled above"),
+                }
+            }
+        }
+        files.retain(|file| file.is_file());
+        files
+    }
+}
+
+impl Display for CacheScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Inputs => "inputs",
+            Self::Submissions => "submissions",
+            Self::Solutions => "solutions",
+            Self::Leaderboards => "leaderboards",
+            Self::Practice => "practice",
+            Self::SampleTests => "tests",
+            Self::Timings => "timings",
+        })
+    }
+}
+
+impl FromStr for CacheScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.to_string() == s)
+            .ok_or_else(|| format!("Unknown cache scope: {s}"))
+    }
+}
+
+/// List the entries of `dir`, or nothing if it can't be read.
+fn entries(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Delete cached data in the given scopes, optionally restricted to a single
+/// year and/or day. Scopes given more than once are only cleaned once.
+///
+/// Every file to be deleted is listed first. If `dry_run` is set, nothing is
+/// deleted; otherwise, if any of the files cannot be downloaded again (such as
+/// your submission history), you will be asked to confirm before anything is
+/// deleted.
+///
+/// Returns the number of files deleted.
+pub fn clean(
+    scopes: &[CacheScope],
+    year: Option<i32>,
+    day: Option<u32>,
+    dry_run: bool,
+) -> usize {
+    let mut scopes = scopes.to_vec();
+    scopes.sort_unstable();
+    scopes.dedup();
+    let targets = scopes
+        .iter()
+        .flat_map(|&scope| {
+            scope
+                .files(year, day)
+                .into_iter()
+                .map(move |file| (scope, file))
+        })
+        .collect::<Vec<_>>();
+    if targets.is_empty() {
+        println!("{}", "Nothing to clean.".green());
+        return 0;
+    }
+
+    for (scope, file) in &targets {
+        let label = format!("[{scope}]");
+        println!(
+            "{} {}",
+            if scope.is_recoverable() {
+                label.yellow()
+            } else {
+                label.red()
+            },
+            file.display()
+        );
+    }
+    if dry_run {
+        println!(
+            "{} {} {}",
+            "Would delete".yellow(),
+            style(targets.len()).blue(),
+            "files.".yellow()
+        );
+        return 0;
+    }
+
+    let unrecoverable = targets
+        .iter()
+        .filter(|(scope, _)| !scope.is_recoverable())
+        .count();
+    if unrecoverable > 0 {
+        eprintln!(
+            "{} {} {}",
+            "Deleting".red(),
+            style(unrecoverable).blue(),
+            "of these files cannot be undone, and they cannot be downloaded again. \
+             Type 'yes' to continue."
+                .red()
+        );
+        eprint!(">>> ");
+        let mut answer = String::new();
+        io::stdin()
+            .read_line(&mut answer)
+            .expect("Failed to read confirmation.");
+        if answer.trim() != "yes" {
+            println!("{}", "Aborted; nothing was deleted.".yellow());
+            return 0;
+        }
+    }
+
+    let mut deleted = 0;
+    for (_, file) in &targets {
+        match fs::remove_file(file) {
+            Ok(()) => deleted += 1,
+            Err(err) => {
+                eprintln!("{} {}: {err}", "Failed to delete".red(), file.display())
+            },
+        }
+    }
+    println!(
+        "{} {} {}",
+        "Deleted".green(),
+        style(deleted).blue(),
+        "files.".green()
+    );
+    deleted
+}
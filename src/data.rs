@@ -1,5 +1,6 @@
 use std::fmt::Display;
 use std::fs;
+use std::time::Duration;
 
 use crossterm::style::Color as Colour;
 use lazy_static::lazy_static;
@@ -39,8 +40,15 @@ lazy_static! {
         path
     };
     pub(crate) static ref TOKEN_FILE: PathDiv = &*DATA_DIR / "token.txt";
+    pub(crate) static ref OFFLINE_FILE: PathDiv = &*DATA_DIR / "offline";
     pub(crate) static ref WAIT_TIME: Regex =
         Regex::new(r"You have (?:(\d+)m )?(\d+)s left to wait.").expect("Infallible");
+    pub(crate) static ref CALENDAR_DAY: Regex = Regex::new(
+        r#"<a[^>]*class="calendar-day(\d+)(?: calendar-(complete|verycomplete))?""#
+    )
+    .expect("Infallible");
+    pub(crate) static ref ACCEPTED_ANSWER: Regex =
+        Regex::new(r"Your puzzle answer was <code>([^<]*)</code>").expect("Infallible");
     pub(crate) static ref RANK: Regex =
         Regex::new(r"You (?:got|achieved) rank (\d+) on this star's leaderboard.")
             .expect("Infallible");
@@ -56,6 +64,40 @@ pub(crate) const GOLD: Colour = Colour::Rgb {
     g: 215,
     b: 135,
 };
+pub(crate) const SILVER: Colour = Colour::Rgb {
+    r: 153,
+    g: 153,
+    b: 204,
+};
+/// The minimum time between requests for the same leaderboard, as requested by
+/// Advent of Code.
+pub(crate) const LEADERBOARD_REFRESH: Duration = Duration::from_secs(15 * 60);
+/// The default minimum time between any two requests.
+pub(crate) const MIN_REQUEST_SPACING: Duration = Duration::from_secs(1);
+/// The default time during which a successful GET request may not be repeated.
+pub(crate) const REPEAT_WINDOW: Duration = Duration::from_secs(30);
+/// The default time to wait for a response before giving up on a request.
+pub(crate) const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// The number of times a failed request is retried before giving up.
+pub(crate) const MAX_RETRIES: u32 = 5;
+/// The delay before the first retry of a failed request; it doubles with each
+/// retry after that.
+pub(crate) const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+/// The default percentage by which a run must be slower than the best
+/// recorded run to count as a regression.
+pub(crate) const REGRESSION_THRESHOLD: f64 = 10.0;
+/// The number of untimed runs before a solution is benchmarked.
+pub(crate) const BENCH_WARM_UP_RUNS: u32 = 3;
+/// The minimum number of timed runs when benchmarking a solution.
+pub(crate) const BENCH_MIN_RUNS: usize = 10;
+/// The maximum number of timed runs when benchmarking a solution.
+pub(crate) const BENCH_MAX_RUNS: usize = 1000;
+/// How long to keep benchmarking a solution, once it has run
+/// [`BENCH_MIN_RUNS`] times.
+pub(crate) const BENCH_TIME: Duration = Duration::from_secs(5);
+/// How often a spinner prints a status line instead, when stderr isn't a
+/// terminal.
+pub(crate) const STATUS_LINE_INTERVAL: Duration = Duration::from_secs(60);
 
 pub(crate) fn leaderboard_url(year: impl Display, day: impl Display) -> String {
     format!("https://adventofcode.com/{year}/leaderboard/day/{day}")
@@ -63,3 +105,15 @@ pub(crate) fn leaderboard_url(year: impl Display, day: impl Display) -> String {
 pub(crate) fn base_url(year: impl Display, day: impl Display) -> String {
     format!("https://adventofcode.com/{year}/day/{day}")
 }
+pub(crate) fn private_leaderboard_url(year: impl Display, id: impl Display) -> String {
+    format!("https://adventofcode.com/{year}/leaderboard/private/view/{id}.json")
+}
+pub(crate) fn personal_stats_url(year: impl Display) -> String {
+    format!("https://adventofcode.com/{year}/leaderboard/self")
+}
+pub(crate) fn global_stats_url(year: impl Display) -> String {
+    format!("https://adventofcode.com/{year}/stats")
+}
+pub(crate) fn calendar_url(year: impl Display) -> String {
+    format!("https://adventofcode.com/{year}")
+}
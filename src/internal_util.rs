@@ -1,9 +1,48 @@
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
-use std::env;
+use std::fmt::{self, Display};
+use std::io::{self, IsTerminal};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{env, fs};
 
 use crossterm::style::{style, Stylize};
 
-use crate::data::{DATA_DIR, GOLD, RANK};
+use crate::data::{
+    ACCEPTED_ANSWER,
+    BENCH_MAX_RUNS,
+    BENCH_MIN_RUNS,
+    BENCH_TIME,
+    BENCH_WARM_UP_RUNS,
+    DATA_DIR,
+    GOLD,
+    OFFLINE_FILE,
+    RANK,
+    RETRY_BASE_DELAY,
+    STATUS_LINE_INTERVAL,
+};
+#[cfg(any(feature = "sync", feature = "async"))]
+use crate::PartOutcome;
+
+/// How long a solution may run for, in seconds, before it's abandoned.
+const TIME_LIMIT_FILE: &str = "time_limit.txt";
+
+/// Set once Advent of Code couldn't be reached, so that no more requests are
+/// attempted.
+static WENT_OFFLINE: AtomicBool = AtomicBool::new(false);
+/// Guards installing the panic hook used by [`catch_panic`].
+static PANIC_HOOK: Once = Once::new();
+
+thread_local! {
+    /// Whether [`catch_panic`] is running on this thread, so panics will be
+    /// reported by it rather than by the default panic hook.
+    static CATCHING_PANICS: Cell<bool> = const { Cell::new(false) };
+    /// Where the last panic caught on this thread happened.
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
 
 pub(crate) fn strip_trailing_nl(mut input: String) -> String {
     let new_len = input
@@ -24,6 +63,14 @@ pub(crate) fn open_page(url: &str) {
     }
 }
 
+/// Check whether the file at `path` exists and was modified less than
+/// `max_age` ago.
+pub(crate) fn is_fresh(path: &Path, max_age: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age < max_age))
+}
+
 /// Analyse and print message
 pub(crate) fn pretty_print(message: &str) {
     if message.starts_with("That's the") {
@@ -40,14 +87,308 @@ pub(crate) fn pretty_print(message: &str) {
     }
 }
 
+/// Read a duration in seconds from `file` in the data directory, falling back
+/// to `default` if it isn't configured.
+pub(crate) fn duration_setting(file: &str, default: Duration) -> Duration {
+    fs::read_to_string(&*DATA_DIR / file)
+        .ok()
+        .and_then(|seconds| seconds.trim().parse().ok())
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .unwrap_or(default)
+}
+
+/// How long to wait before retry number `attempt` (counting from 0) of a
+/// failed request: exponential backoff, with jitter so that everyone retrying
+/// at 05:00 UTC doesn't retry in lockstep.
+pub(crate) fn backoff(attempt: u32) -> Duration {
+    // no need for a proper RNG; the clock's nanoseconds are unpredictable
+    // enough to spread retries out
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.subsec_nanos());
+    let jitter = 0.5 + f64::from(nanos % 1000) / 1000.0;
+    RETRY_BASE_DELAY.mul_f64(f64::from(2u32.pow(attempt)) * jitter)
+}
+
+/// Check whether the puzzle page shows `answer` as accepted for `part`.
+///
+/// Part 2 of day 25 has no answer; it's accepted once both parts are complete.
+pub(crate) fn answer_accepted(page: &str, day: u32, part: u32, answer: &str) -> bool {
+    if day == 25 && part == 2 {
+        return page.contains("Both parts of this puzzle are complete");
+    }
+    ACCEPTED_ANSWER
+        .captures_iter(page)
+        .nth(part as usize - 1)
+        .is_some_and(|captures| {
+            captures
+                .get(1)
+                .expect("ACCEPTED_ANSWER regex has one capture")
+                .as_str()
+                == answer
+        })
+}
+
 pub(crate) fn is_practice_mode() -> bool {
     env::args().any(|arg| arg == "--practice")
 }
 
+/// Whether `raoc` is in offline mode, and will only use cached data.
+///
+/// Offline mode is enabled by passing `--offline` on the command line, by
+/// creating a file named `offline` in the data directory, or automatically
+/// once Advent of Code can't be reached.
+#[must_use]
+pub fn is_offline() -> bool {
+    WENT_OFFLINE.load(Ordering::Relaxed)
+        || env::args().any(|arg| arg == "--offline")
+        || OFFLINE_FILE.exists()
+}
+
+/// Switch to offline mode for the rest of the process, after failing to
+/// connect to Advent of Code.
+pub(crate) fn go_offline() {
+    if !WENT_OFFLINE.swap(true, Ordering::Relaxed) {
+        eprintln!(
+            "{}",
+            "Couldn't connect to Advent of Code; continuing offline, using only \
+             cached data."
+                .yellow()
+        );
+    }
+}
+
 pub(crate) fn must_run_solutions() -> bool {
     env::args().any(|arg| arg == "--force-run")
 }
 
+pub(crate) fn is_verify_mode() -> bool {
+    env::args().any(|arg| arg == "--verify")
+}
+
+pub(crate) fn is_bench_mode() -> bool {
+    env::args().any(|arg| arg == "--bench")
+}
+
+/// How long a solution may run before it's abandoned, if there's a limit.
+///
+/// The limit is set (in seconds) by passing `--time-limit=SECONDS` on the
+/// command line, or in `time_limit.txt` in the data directory.
+pub(crate) fn time_limit() -> Option<Duration> {
+    env::args()
+        .find_map(|arg| arg.strip_prefix("--time-limit=").map(str::to_string))
+        .or_else(|| fs::read_to_string(&*DATA_DIR / TIME_LIMIT_FILE).ok())
+        .and_then(|seconds| seconds.trim().parse().ok())
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+}
+
+/// Run `f`, catching an unwinding panic and returning its message (and where
+/// it happened) instead of letting the default panic hook print it.
+pub(crate) fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    PANIC_HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if CATCHING_PANICS.get() {
+                PANIC_LOCATION.set(info.location().map(ToString::to_string));
+            } else {
+                default_hook(info);
+            }
+        }));
+    });
+    let was_catching = CATCHING_PANICS.replace(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING_PANICS.set(was_catching);
+    result.map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| (*message).to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".into());
+        match PANIC_LOCATION.take() {
+            Some(location) => format!("{message} (at {location})"),
+            None => message,
+        }
+    })
+}
+
+/// Unwrap the result of a solution run by [`work_with_time_limit`] and
+/// [`catch_panic`], or report that it panicked or ran out of time.
+///
+/// [`work_with_time_limit`]: crate::sync_work_with_time_limit
+#[cfg(any(feature = "sync", feature = "async"))]
+pub(crate) fn finished_run<T>(
+    day: u32,
+    part: u32,
+    start: Instant,
+    time_limit: Option<Duration>,
+    result: Option<Result<T, String>>,
+) -> Result<T, PartOutcome> {
+    let outcome = match result {
+        Some(Ok(result)) => return Ok(result),
+        Some(Err(message)) => PartOutcome::Panicked {
+            message,
+            elapsed: start.elapsed(),
+        },
+        None => PartOutcome::TimedOut(
+            time_limit.expect("Only a limited solution can time out"),
+        ),
+    };
+    let report = match &outcome {
+        PartOutcome::Panicked { message, elapsed } => format!(
+            "{} {}{} {}",
+            "panicked after".red(),
+            format!("{elapsed:.2?}").blue(),
+            ':'.red(),
+            message.as_str().red(),
+        ),
+        PartOutcome::TimedOut(limit) => format!(
+            "{} {}{}",
+            "timed out after".red(),
+            format!("{limit:.2?}").blue(),
+            "; its answer won't be checked.".red(),
+        ),
+        PartOutcome::Finished => unreachable!("Finished runs were returned above"),
+    };
+    println!(
+        "Day {} part {}: {report}",
+        style(day).blue(),
+        style(part).blue()
+    );
+    Err(outcome)
+}
+
+/// Whether spinners should be animated in place, rather than printing an
+/// occasional status line.
+///
+/// Spinners are animated if stderr is a terminal, unless `--no-spinner` is
+/// passed on the command line; passing `--spinner` animates them regardless.
+pub(crate) fn animate_spinners() -> bool {
+    if env::args().any(|arg| arg == "--spinner") {
+        true
+    } else if env::args().any(|arg| arg == "--no-spinner") {
+        false
+    } else {
+        io::stderr().is_terminal()
+    }
+}
+
+/// Format a duration as `HH:MM:SS`.
+pub(crate) fn format_clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// The spinner shown by `wait` and `work`: redrawn in place every frame if
+/// it's animated, or printed as a status line once a minute otherwise, so that
+/// logs aren't flooded with frames.
+#[cfg(any(feature = "sync", feature = "async"))]
+pub(crate) struct Spinner {
+    animated: bool,
+    next_status_line: Duration,
+}
+
+#[cfg(any(feature = "sync", feature = "async"))]
+impl Spinner {
+    pub(crate) fn new() -> Self {
+        Self {
+            animated: animate_spinners(),
+            next_status_line: Duration::ZERO,
+        }
+    }
+
+    /// Draw the spinner, which has been running for `elapsed`, with `msg`
+    /// before it and `status` after.
+    pub(crate) fn draw(&mut self, msg: &impl Display, elapsed: Duration, status: &str) {
+        if self.animated {
+            let frame = match (elapsed.as_millis() / 80) % 10 {
+                0 => '⠋',
+                1 => '⠙',
+                2 => '⠹',
+                3 => '⠸',
+                4 => '⠼',
+                5 => '⠴',
+                6 => '⠦',
+                7 => '⠧',
+                8 => '⠇',
+                9 => '⠏',
+                _ => unreachable!(),
+            };
+            eprint!("\r{msg} {} {status}", frame.yellow());
+        } else if elapsed >= self.next_status_line {
+            eprintln!("{msg} {status}");
+            self.next_status_line = elapsed + STATUS_LINE_INTERVAL;
+        }
+    }
+
+    /// End the spinner's line, once it's done.
+    pub(crate) fn finish(&self) {
+        if self.animated {
+            eprintln!();
+        }
+    }
+}
+
+/// Report that `part` of `day` was skipped, and why.
+pub(crate) fn report_skipped(day: u32, part: u32, reason: &str) {
+    println!(
+        "{} {} {} {}{} {}",
+        "Day".yellow(),
+        style(day).blue(),
+        "part".yellow(),
+        style(part).blue(),
+        ':'.yellow(),
+        format!("skipped, as {reason}.").yellow(),
+    );
+}
+
+/// Skip `part` of `day` in `--bench` mode, as the solution can only be run
+/// once; `benchable` is the function which can benchmark it instead.
+#[cfg(any(feature = "sync", feature = "async"))]
+pub(crate) fn skip_bench(day: u32, part: u32, benchable: &str) -> PartOutcome {
+    if !(day == 25 && part == 2) {
+        report_skipped(
+            day,
+            part,
+            &format!("benchmarking needs `{benchable}` to run the solution repeatedly"),
+        );
+    }
+    PartOutcome::Finished
+}
+
+/// Report whether the solution to `part` of `day` produced the accepted
+/// answer, and how long it took.
+pub(crate) fn report_verification(
+    day: u32,
+    part: u32,
+    expected: &str,
+    answer: Option<String>,
+    elapsed: Duration,
+) {
+    let result = match answer {
+        Some(answer) if answer == expected => format!("{}", "passed".green()),
+        Some(answer) => format!(
+            "{} {} {} {}{}",
+            "failed: expected".red(),
+            expected.blue(),
+            "but got".red(),
+            answer.blue(),
+            '.'.red()
+        ),
+        None => format!("{}", "failed: the solution returned no answer.".red()),
+    };
+    println!(
+        "Day {} part {}: {result} {}",
+        style(day).blue(),
+        style(part).blue(),
+        format!("({elapsed:.2?})").yellow(),
+    );
+}
+
 #[cfg(any(feature = "sync", feature = "async"))]
 #[allow(deprecated, clippy::cast_precision_loss)]
 pub(crate) fn get_leaderboard_time(day: u32, time: &str) -> f64 {
@@ -62,6 +403,41 @@ pub(crate) fn get_leaderboard_time(day: u32, time: &str) -> f64 {
     .as_secs_f64()
 }
 
+/// Estimate the rank that a solve taking `solve_time` seconds would have
+/// achieved, by fitting a power law to the times on the global leaderboard and
+/// extrapolating past its end.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn extrapolate_rank(leaderboard: &[f64], solve_time: f64) -> Option<f64> {
+    let points = leaderboard
+        .iter()
+        .enumerate()
+        .filter(|&(_, &time)| time > 0.0)
+        .map(|(i, &time)| (time.ln(), ((i + 1) as f64).ln()))
+        .collect::<Vec<_>>();
+    if points.len() < 2 || solve_time <= 0.0 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|&(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|&(_, y)| y).sum::<f64>() / n;
+    let covariance = points
+        .iter()
+        .map(|&(x, y)| (x - mean_x) * (y - mean_y))
+        .sum::<f64>();
+    let variance = points
+        .iter()
+        .map(|&(x, _)| (x - mean_x).powi(2))
+        .sum::<f64>();
+    // equal times don't quite cancel out, so don't compare with 0 exactly
+    if variance <= f64::EPSILON {
+        return None;
+    }
+    let slope = covariance / variance;
+    let intercept = mean_y - slope * mean_x;
+    let rank = (intercept + slope * solve_time.ln()).exp();
+    Some(rank.max(leaderboard.len() as f64 + 1.0))
+}
+
 pub(crate) fn format_time(seconds: f64) -> String {
     let (minutes, seconds) = ((seconds / 60.0).trunc(), seconds % 60.0);
     let (hours, minutes) = ((minutes / 60.0).trunc(), minutes % 60.0);
@@ -88,6 +464,218 @@ pub(crate) fn message_from_body(body: &str) -> String {
     article.inner_text(page.parser()).to_string()
 }
 
+/// Retrieve the text of the first `<pre>` block in the page, if there is one.
+#[cfg(feature = "web")]
+pub(crate) fn pre_from_body(body: &str) -> Option<String> {
+    use tl::ParserOptions;
+
+    let page =
+        tl::parse(body, ParserOptions::new()).expect("Failed to parse response.");
+    let pre = page
+        .query_selector("pre")
+        .expect("Failed to compile the 'pre' query")
+        .next()?
+        .get(page.parser())
+        .expect("Failed to retrieve node associated with the `pre` tag");
+    Some(pre.inner_text(page.parser()).to_string())
+}
+
+/// Read a cached response of any age, for when Advent of Code couldn't be
+/// reached to refresh it.
+#[cfg(feature = "web")]
+pub(crate) fn read_stale<T: serde::de::DeserializeOwned>(
+    cache_file: &Path,
+) -> Option<T> {
+    fs::read_to_string(cache_file)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+}
+
+/// A response from Advent of Code, with the body already read.
+#[cfg(feature = "web")]
+pub(crate) struct Reply {
+    pub(crate) status: reqwest::StatusCode,
+    pub(crate) body: String,
+}
+
+/// Why a request didn't produce a [`Reply`].
+#[cfg(feature = "web")]
+pub(crate) enum SendError {
+    /// The request never reached Advent of Code, so it's safe to send again.
+    NotSent(reqwest::Error),
+    /// The request wasn't attempted, as `raoc` is offline.
+    Offline,
+    /// The request may have reached Advent of Code, but no usable response
+    /// came back.
+    MaybeSent,
+}
+
+#[cfg(feature = "web")]
+impl Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotSent(err) => {
+                write!(f, "Advent of Code couldn't be reached ({err})")
+            },
+            Self::Offline => f.write_str("raoc is offline"),
+            Self::MaybeSent => {
+                f.write_str("no usable response came back from Advent of Code")
+            },
+        }
+    }
+}
+
+/// Warn that `what` couldn't be refreshed, and that a cached copy is being used
+/// instead.
+#[cfg(feature = "web")]
+pub(crate) fn warn_stale(what: &str, err: &SendError) {
+    eprintln!(
+        "{}",
+        format!(
+            "Warning: Couldn't refresh {what}, as {err}. Using the cached copy, which \
+             may be out of date."
+        )
+        .yellow()
+    );
+}
+
+/// Check whether Advent of Code served a page for a logged-out user, which
+/// happens when the session token has expired.
+pub(crate) fn is_logged_out(body: &str) -> bool {
+    body.contains("/auth/login")
+}
+
+/// Why a response body is not a puzzle input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InputProblem {
+    Empty,
+    TooEarly,
+    LoggedOut,
+    Html,
+}
+
+impl Display for InputProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Empty => "the input is empty",
+            Self::TooEarly => "the puzzle has not unlocked yet",
+            Self::LoggedOut => "you are not logged in",
+            Self::Html => "Advent of Code sent back a web page",
+        })
+    }
+}
+
+/// Check that a response body looks like a puzzle input, rather than one of
+/// the notices Advent of Code serves in place of it.
+pub(crate) fn check_input(body: &str) -> Result<(), InputProblem> {
+    if body.trim().is_empty() {
+        Err(InputProblem::Empty)
+    } else if body.contains("Please don't repeatedly request this endpoint") {
+        Err(InputProblem::TooEarly)
+    } else if body.contains("Please log in to get your puzzle input")
+        || is_logged_out(body)
+    {
+        Err(InputProblem::LoggedOut)
+    } else if body.trim_start().starts_with('<')
+        && (body.contains("<html") || body.contains("<!DOCTYPE"))
+    {
+        Err(InputProblem::Html)
+    } else {
+        Ok(())
+    }
+}
+
+/// Summary statistics for a set of timings.
+struct TimingStats {
+    min: Duration,
+    median: Duration,
+    mean: Duration,
+    stddev: Duration,
+}
+
+impl TimingStats {
+    #[allow(clippy::cast_precision_loss)]
+    fn new(timings: &mut [Duration]) -> Self {
+        timings.sort_unstable();
+        let mean = timings.iter().sum::<Duration>() / timings.len() as u32;
+        let variance = timings
+            .iter()
+            .map(|&time| (time.as_secs_f64() - mean.as_secs_f64()).powi(2))
+            .sum::<f64>()
+            / timings.len() as f64;
+        Self {
+            min: timings[0],
+            median: timings[timings.len() / 2],
+            mean,
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        }
+    }
+}
+
+impl Display for TimingStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "min {}, median {}, mean {}, stddev {}",
+            format!("{:.2?}", self.min).blue(),
+            format!("{:.2?}", self.median).blue(),
+            format!("{:.2?}", self.mean).blue(),
+            format!("{:.2?}", self.stddev).blue(),
+        )
+    }
+}
+
+/// Timings for repeated runs of a solution, parsing and solving separately.
+pub(crate) struct Benchmark {
+    start: Instant,
+    warm_up_runs: u32,
+    parse: Vec<Duration>,
+    solve: Vec<Duration>,
+}
+
+impl Benchmark {
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            warm_up_runs: 0,
+            parse: Vec::new(),
+            solve: Vec::new(),
+        }
+    }
+
+    /// Whether the solution should be run again: after warming up, it runs at
+    /// least [`BENCH_MIN_RUNS`] times, then until [`BENCH_TIME`] has passed.
+    pub(crate) fn keep_going(&self) -> bool {
+        self.parse.len() < BENCH_MIN_RUNS
+            || (self.start.elapsed() < BENCH_TIME && self.parse.len() < BENCH_MAX_RUNS)
+    }
+
+    /// Record the time taken by one run; warm-up runs are discarded.
+    pub(crate) fn record(&mut self, parse: Duration, solve: Duration) {
+        if self.warm_up_runs < BENCH_WARM_UP_RUNS {
+            self.warm_up_runs += 1;
+            self.start = Instant::now();
+        } else {
+            self.parse.push(parse);
+            self.solve.push(solve);
+        }
+    }
+
+    pub(crate) fn report(mut self, day: u32, part: u32, answer: Option<String>) {
+        println!(
+            "Day {} part {} {}",
+            style(day).blue(),
+            style(part).blue(),
+            format!("({} runs):", self.parse.len()).yellow(),
+        );
+        println!("    parse: {}", TimingStats::new(&mut self.parse));
+        println!("    solve: {}", TimingStats::new(&mut self.solve));
+        if let Some(answer) = answer {
+            println!("    answer: {}", answer.blue());
+        }
+    }
+}
+
 pub(crate) fn print_rank(msg: &str) {
     if let Some(rank) = RANK.captures(msg) {
         pretty_print(&format!(
@@ -148,3 +736,130 @@ pub(crate) fn test(part: u32, answer: &str, expected: &str) {
         answer.blue(),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        answer_accepted,
+        backoff,
+        check_input,
+        extrapolate_rank,
+        InputProblem,
+    };
+    use crate::data::RETRY_BASE_DELAY;
+
+    /// The end of a puzzle page once both parts have been solved.
+    const SOLVED_PAGE: &str = r#"<article class="day-desc"><h2>--- Day 1: Trebuchet?! ---</h2></article>
+<p>Your puzzle answer was <code>54573</code>.</p><article class="day-desc"><h2 id="part2">--- Part Two ---</h2></article>
+<p>Your puzzle answer was <code>54591</code>.</p><p class="day-success">Both parts of this puzzle are complete! They provide two gold stars: **</p>"#;
+
+    /// What `/input` returns without a valid session token.
+    const LOGGED_OUT_INPUT: &str =
+        "Puzzle inputs differ by user.  Please log in to get your puzzle input.\n";
+
+    #[test]
+    fn extrapolated_rank_follows_the_leaderboard() {
+        // rank = time, exactly
+        let leaderboard = (1..=100).map(f64::from).collect::<Vec<_>>();
+        let rank = extrapolate_rank(&leaderboard, 200.0).expect("Enough points");
+        assert!((rank - 200.0).abs() < 1e-6, "{rank}");
+    }
+
+    #[test]
+    fn extrapolated_rank_is_past_the_leaderboard() {
+        let leaderboard = (1..=100).map(f64::from).collect::<Vec<_>>();
+        // a time which would have made the leaderboard can't be extrapolated
+        // onto it
+        assert_eq!(extrapolate_rank(&leaderboard, 50.0), Some(101.0));
+    }
+
+    #[test]
+    fn extrapolated_rank_needs_a_leaderboard() {
+        assert_eq!(extrapolate_rank(&[], 100.0), None);
+        assert_eq!(extrapolate_rank(&[30.0], 100.0), None);
+        // unfilled places don't count
+        assert_eq!(extrapolate_rank(&[30.0, 0.0, 0.0], 100.0), None);
+        assert_eq!(extrapolate_rank(&[30.0, 40.0], 0.0), None);
+    }
+
+    #[test]
+    fn extrapolated_rank_needs_variance() {
+        assert_eq!(extrapolate_rank(&[60.0; 100], 100.0), None);
+    }
+
+    #[test]
+    fn input_is_accepted() {
+        assert_eq!(check_input("1abc2\npqr3stu8vwx"), Ok(()));
+        assert_eq!(check_input("<-<-v\n>>^>v"), Ok(()));
+    }
+
+    #[test]
+    fn notices_are_not_input() {
+        assert_eq!(check_input(""), Err(InputProblem::Empty));
+        assert_eq!(check_input(" \n\n"), Err(InputProblem::Empty));
+        assert_eq!(
+            check_input(
+                "Please don't repeatedly request this endpoint before it unlocks! The \
+                 calendar countdown is synchronized with the server time; the link \
+                 will be enabled on the calendar the instant this puzzle becomes \
+                 available."
+            ),
+            Err(InputProblem::TooEarly)
+        );
+        assert_eq!(check_input(LOGGED_OUT_INPUT), Err(InputProblem::LoggedOut));
+        assert_eq!(
+            check_input(r#"<html><a href="/2023/auth/login">[Log In]</a></html>"#),
+            Err(InputProblem::LoggedOut)
+        );
+        assert_eq!(
+            check_input("<!DOCTYPE html>\n<html lang=\"en-us\"></html>"),
+            Err(InputProblem::Html)
+        );
+    }
+
+    #[test]
+    fn accepted_answers_are_found_by_part() {
+        assert!(answer_accepted(SOLVED_PAGE, 1, 1, "54573"));
+        assert!(answer_accepted(SOLVED_PAGE, 1, 2, "54591"));
+        assert!(!answer_accepted(SOLVED_PAGE, 1, 1, "54591"));
+        assert!(!answer_accepted(SOLVED_PAGE, 1, 1, "5457"));
+    }
+
+    #[test]
+    fn unsolved_parts_are_not_accepted() {
+        let page = SOLVED_PAGE
+            .split_once("<article class=\"day-desc\"><h2 id=\"part2\">")
+            .expect("The fixture has a part 2")
+            .0;
+        assert!(answer_accepted(page, 1, 1, "54573"));
+        assert!(!answer_accepted(page, 1, 2, "54591"));
+        assert!(!answer_accepted(LOGGED_OUT_INPUT, 1, 1, "54573"));
+    }
+
+    #[test]
+    fn day_25_part_2_is_accepted_with_both_parts() {
+        assert!(answer_accepted(SOLVED_PAGE, 25, 2, ""));
+        assert!(!answer_accepted(
+            "<p>Your puzzle answer was <code>1</code>.</p>",
+            25,
+            2,
+            ""
+        ));
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter() {
+        for attempt in 0..5 {
+            let base = RETRY_BASE_DELAY * 2u32.pow(attempt);
+            let delay = backoff(attempt);
+            assert!(
+                delay >= base / 2 && delay < base * 3 / 2,
+                "attempt {attempt}: {delay:?}"
+            );
+        }
+        assert!(backoff(4) > backoff(0) * 5);
+        assert!(backoff(0) > Duration::ZERO);
+    }
+}
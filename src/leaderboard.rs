@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use crossterm::style::Stylize;
+use serde::{Deserialize, Serialize};
+
+use crate::data::{GOLD, SILVER};
+use crate::internal_util::format_time;
+
+/// A private leaderboard, as returned by Advent of Code's JSON API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivateLeaderboard {
+    /// The user ID of the leaderboard's owner.
+    pub owner_id: u64,
+    /// The year of the event this leaderboard is for.
+    pub event: String,
+    /// The members of the leaderboard, keyed by their user ID.
+    pub members: HashMap<String, Member>,
+}
+
+/// A member of a private leaderboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Member {
+    /// The member's user ID.
+    pub id: u64,
+    /// The member's display name, if they have set one.
+    pub name: Option<String>,
+    /// The number of stars the member has collected this event.
+    pub stars: u32,
+    /// The member's score on this leaderboard.
+    pub local_score: u32,
+    /// The member's score on the global leaderboard.
+    pub global_score: u32,
+    /// The Unix timestamp at which the member collected their latest star.
+    pub last_star_ts: i64,
+    /// The stars collected by the member, keyed by day.
+    pub completion_day_level: HashMap<String, DayCompletion>,
+}
+
+/// The stars a member has collected for a single day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayCompletion {
+    /// The star for part 1.
+    #[serde(rename = "1")]
+    pub part_1: Star,
+    /// The star for part 2, if it has been collected.
+    #[serde(rename = "2")]
+    pub part_2: Option<Star>,
+}
+
+/// A single collected star.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Star {
+    /// The Unix timestamp at which the star was collected.
+    pub get_star_ts: i64,
+    /// The global order in which the star was collected.
+    #[serde(default)]
+    pub star_index: u64,
+}
+
+impl Member {
+    /// The member's display name, falling back to their anonymous name.
+    #[must_use]
+    pub fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("(anonymous user #{})", self.id))
+    }
+
+    /// The stars collected by the member for `day`.
+    #[must_use]
+    pub fn day(&self, day: u32) -> Option<&DayCompletion> {
+        self.completion_day_level.get(&day.to_string())
+    }
+
+    /// The time, in seconds, between the member collecting the part 1 and
+    /// part 2 stars for `day`.
+    #[must_use]
+    pub fn part_2_delta(&self, day: u32) -> Option<i64> {
+        let completion = self.day(day)?;
+        Some(completion.part_2.as_ref()?.get_star_ts - completion.part_1.get_star_ts)
+    }
+}
+
+impl PrivateLeaderboard {
+    /// The members of the leaderboard, ordered by their position on it.
+    #[must_use]
+    pub fn standings(&self) -> Vec<&Member> {
+        let mut members = self.members.values().collect::<Vec<_>>();
+        members.sort_by_key(|member| {
+            (
+                std::cmp::Reverse(member.local_score),
+                member.last_star_ts,
+                member.id,
+            )
+        });
+        members
+    }
+
+    /// The latest day for which any member has collected a star.
+    #[must_use]
+    pub fn last_day(&self) -> u32 {
+        self.members
+            .values()
+            .flat_map(|member| member.completion_day_level.keys())
+            .filter_map(|day| day.parse().ok())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Display for PrivateLeaderboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let standings = self.standings();
+        let last_day = self.last_day();
+        let name_width = standings
+            .iter()
+            .map(|member| member.display_name().chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+
+        write!(
+            f,
+            "{:>4} {:>5} {:>5} {:<name_width$}",
+            "Rank", "Score", "Stars", "Name"
+        )?;
+        for day in 1..=last_day {
+            write!(f, " {day:>2}")?;
+        }
+        writeln!(f)?;
+        for (rank, member) in standings.iter().enumerate() {
+            write!(
+                f,
+                "{:>3}) {:>5} {:>5} {:<name_width$}",
+                rank + 1,
+                member.local_score,
+                member.stars,
+                member.display_name(),
+            )?;
+            for day in 1..=last_day {
+                let star = match member.day(day) {
+                    Some(DayCompletion {
+                        part_2: Some(_), ..
+                    }) => '*'.with(GOLD),
+                    Some(_) => '*'.with(SILVER),
+                    None => '.'.dark_grey(),
+                };
+                write!(f, "  {star}")?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{}", "Time from part 1 to part 2:".yellow())?;
+        write!(f, "{:<name_width$}", "Name")?;
+        for day in 1..=last_day {
+            write!(f, " {day:>8}")?;
+        }
+        writeln!(f)?;
+        for member in standings {
+            write!(f, "{:<name_width$}", member.display_name())?;
+            for day in 1..=last_day {
+                #[allow(clippy::cast_precision_loss)]
+                match member.part_2_delta(day) {
+                    Some(delta) => {
+                        write!(
+                            f,
+                            " {}",
+                            format!("{:>8}", format_time(delta as f64)).blue()
+                        )?;
+                    },
+                    None => write!(f, " {}", format!("{:>8}", '-').dark_grey())?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
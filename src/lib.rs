@@ -1,7 +1,10 @@
 #![doc = include_str!("../README.md")]
 #![feature(negative_impls, auto_traits)]
+mod alloc;
 #[cfg(feature = "async")]
 mod async_impl;
+#[cfg(feature = "web")]
+mod audit;
 #[cfg(feature = "async")]
 #[cfg_attr(
     feature = "sync",
@@ -9,13 +12,23 @@ mod async_impl;
 )]
 #[allow(unused_imports)]
 pub use async_impl::{
+    calendar as async_calendar,
     fetch as async_fetch,
+    fetch_year as async_fetch_year,
+    fetch_years as async_fetch_years,
+    flush_submissions as async_flush_submissions,
     get_sample_input as async_get_sample_input,
+    global_stats as async_global_stats,
     lazy_submit as async_lazy_submit,
+    lazy_submit_benchable as async_lazy_submit_benchable,
     lazy_submit_part as async_lazy_submit_part,
+    lazy_submit_part_benchable as async_lazy_submit_part_benchable,
+    personal_stats as async_personal_stats,
+    private_leaderboard as async_private_leaderboard,
     submit as async_submit,
     wait as async_wait,
     work as async_work,
+    work_with_time_limit as async_work_with_time_limit,
     *,
 };
 #[cfg(feature = "sync")]
@@ -23,19 +36,98 @@ mod sync_impl;
 #[cfg(feature = "sync")]
 #[allow(unused_imports)]
 pub use sync_impl::{
+    calendar as sync_calendar,
     fetch as sync_fetch,
+    fetch_year as sync_fetch_year,
+    fetch_years as sync_fetch_years,
+    flush_submissions as sync_flush_submissions,
     get_sample_input as sync_get_sample_input,
+    global_stats as sync_global_stats,
     lazy_submit as sync_lazy_submit,
+    lazy_submit_abandonable as sync_lazy_submit_abandonable,
+    lazy_submit_benchable as sync_lazy_submit_benchable,
     lazy_submit_part as sync_lazy_submit_part,
+    lazy_submit_part_abandonable as sync_lazy_submit_part_abandonable,
+    lazy_submit_part_benchable as sync_lazy_submit_part_benchable,
+    personal_stats as sync_personal_stats,
+    private_leaderboard as sync_private_leaderboard,
     submit as sync_submit,
     wait as sync_wait,
     work as sync_work,
+    work_with_time_limit as sync_work_with_time_limit,
     *,
 };
+mod cache;
+#[cfg(feature = "web")]
+mod calendar;
+mod clean;
 mod data;
 mod internal_util;
+#[cfg(feature = "web")]
+mod leaderboard;
 mod maybe_display;
+#[cfg(feature = "web")]
+mod outcome;
+#[cfg(feature = "web")]
+mod progress;
+#[cfg(feature = "web")]
+mod queue;
+mod regression;
+#[cfg(feature = "web")]
+mod stats;
+#[cfg(feature = "web")]
+mod summary;
+#[cfg(feature = "web")]
+mod throttle;
+#[cfg(feature = "web")]
+mod timings;
+pub use alloc::{CountingAllocator, MemoryUsage};
+
+#[cfg(feature = "web")]
+pub use audit::{
+    query_requests,
+    request_log,
+    RequestFailure,
+    RequestFilter,
+    RequestRecord,
+};
+pub use cache::CacheIssue;
+#[cfg(feature = "web")]
+pub use cache::{migrate_cache, validate_cache};
+#[cfg(feature = "web")]
+pub use calendar::{cached_star_totals, Calendar, StarState};
+pub use clean::{clean, CacheScope};
+pub use internal_util::is_offline;
+#[cfg(feature = "web")]
+pub use leaderboard::{DayCompletion, Member, PrivateLeaderboard, Star};
 pub use maybe_display::MaybeDisplay;
+#[cfg(feature = "web")]
+pub use outcome::PartOutcome;
+#[cfg(feature = "web")]
+pub use progress::{progress, ProgressHandle};
+#[cfg(feature = "web")]
+pub use queue::{queued_answers, QueuedAnswer};
+#[doc(hidden)]
+pub use regression::run_regression_test;
+#[cfg(feature = "web")]
+pub use stats::{DayStats, GlobalDayStats, GlobalStats, PartStats, PersonalStats};
+#[cfg(feature = "web")]
+pub use summary::{
+    cache_summary,
+    CacheSummary,
+    PartSummary,
+    PracticeAttempt,
+    PuzzleSummary,
+};
+#[cfg(feature = "web")]
+pub use timings::{
+    regression_threshold,
+    run_timings,
+    timing_report,
+    PartTrend,
+    RunTimings,
+    TimingReport,
+};
 
 #[cfg(all(feature = "simd", not(feature = "web")))]
 compile_error!(
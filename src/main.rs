@@ -0,0 +1,452 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use chrono::{Datelike, NaiveDate, Utc};
+use crossterm::style::{style, Stylize};
+use raoc::{
+    cache_summary,
+    cached_star_totals,
+    clean,
+    is_offline,
+    migrate_cache,
+    query_requests,
+    queued_answers,
+    regression_threshold,
+    sync_calendar,
+    sync_fetch_years,
+    sync_flush_submissions,
+    timing_report,
+    validate_cache,
+    CacheScope,
+    Calendar,
+    RequestFilter,
+};
+
+const USAGE: &str = "\
+Usage: raoc <command> [options]
+
+Commands:
+    stars [YEAR] [--offline]
+        Show the stars collected for each day of YEAR (default: the latest
+        event), and the total stars collected for every cached year. With
+        --offline (or in offline mode), the calendar is inferred from cached
+        solutions only.
+    fetch [--delay SECONDS] YEAR|FIRST..LAST
+        Download every unlocked input for YEAR (or each year from FIRST to
+        LAST) that isn't already cached, waiting at least SECONDS (default:
+        5) between requests. Stops if no token is saved, or at the first
+        authentication error.
+    summary [YEAR]
+        Summarise everything cached for each puzzle (optionally only for
+        YEAR): inputs, accepted answers, wrong submissions, known bounds and
+        practice runs. Makes no requests.
+    clean [--dry-run] [--year YEAR] [--day DAY] SCOPE...
+        Delete cached data. SCOPE is one or more of inputs, submissions,
+        solutions, leaderboards, practice, tests, timings, or all. Every file
+        is listed before deletion; with --dry-run, nothing is deleted. You will
+        be asked to confirm before deleting anything that can't be downloaded
+        again.
+    log [--today | --date DATE] [--year YEAR] [--day DAY] [--api NAME] [--list]
+        Count the requests made to Advent of Code, optionally only those made
+        today or on DATE (YYYY-MM-DD, in UTC), for a given puzzle, or by a
+        given library function (such as fetch or submit). With --list, every
+        matching request is shown.
+    timings [YEAR] [--threshold PERCENT]
+        Show how the runtime (parsing and solving) of each solution has changed
+        over its recorded runs, flagging any whose latest run is more than
+        PERCENT (default: 10, or the contents of regression_threshold.txt in
+        the data directory) slower than its best run with the same build
+        profile. Exits with an error if any regressions are found.
+    flush [--list]
+        Submit every answer which couldn't be delivered when it was first
+        submitted (for example, because the network was down), oldest first.
+        Answers to parts which have been solved since are not sent. With
+        --list, the queued answers are shown without submitting anything.
+    validate
+        Migrate the cache to the current layout, then check it for problems,
+        such as accepted answers with no recorded submission or files that
+        can't be parsed.";
+
+/// The most recent year with an event that has started.
+fn latest_year() -> i32 {
+    let now = Utc::now();
+    if now.month() == 12 {
+        now.year()
+    } else {
+        now.year() - 1
+    }
+}
+
+fn parse_year(arg: &str) -> Option<i32> {
+    arg.parse().ok().filter(|&year| year >= 2015)
+}
+
+fn stars(args: &[String]) -> ExitCode {
+    let mut year = latest_year();
+    let mut offline = false;
+    for arg in args {
+        match arg.as_str() {
+            "--offline" => offline = true,
+            arg => match parse_year(arg) {
+                Some(parsed) => year = parsed,
+                None => {
+                    eprintln!("{} {arg}", "Invalid year:".red());
+                    return ExitCode::FAILURE;
+                },
+            },
+        }
+    }
+
+    let calendar = if offline || is_offline() {
+        Calendar::from_cache(year)
+    } else {
+        sync_calendar(year)
+    };
+    println!("{calendar}");
+
+    let totals = cached_star_totals();
+    if !totals.is_empty() {
+        println!("{}", "Stars collected (from cached solutions):".yellow());
+        for (year, stars) in &totals {
+            println!("{year}: {}", style(stars).blue());
+        }
+        println!(
+            "{} {}",
+            "Total:".yellow(),
+            style(totals.values().sum::<u32>()).blue()
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+fn fetch(args: &[String]) -> ExitCode {
+    let mut years = None;
+    let mut delay = Duration::from_secs(5);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--delay" => match args
+                .next()
+                .and_then(|arg| arg.parse().ok())
+                .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            {
+                Some(parsed) => delay = parsed,
+                None => {
+                    eprintln!("{}", "--delay requires a number of seconds".red());
+                    return ExitCode::FAILURE;
+                },
+            },
+            arg => {
+                let range = match arg.split_once("..") {
+                    Some((first, last)) => parse_year(first)
+                        .zip(parse_year(last))
+                        .filter(|(first, last)| first <= last),
+                    None => parse_year(arg).map(|year| (year, year)),
+                };
+                match range {
+                    Some((first, last)) => years = Some(first..=last),
+                    None => {
+                        eprintln!("{} {arg}", "Invalid year or range:".red());
+                        return ExitCode::FAILURE;
+                    },
+                }
+            },
+        }
+    }
+    let Some(years) = years else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    let fetched = sync_fetch_years(years, delay);
+    println!(
+        "{} {} {}",
+        "Fetched".green(),
+        style(fetched.len()).blue(),
+        if fetched.len() == 1 {
+            "input."
+        } else {
+            "inputs."
+        }
+        .green()
+    );
+    ExitCode::SUCCESS
+}
+
+fn summary(args: &[String]) -> ExitCode {
+    let year = match args {
+        [] => None,
+        [year] => match parse_year(year) {
+            Some(year) => Some(year),
+            None => {
+                eprintln!("{} {year}", "Invalid year:".red());
+                return ExitCode::FAILURE;
+            },
+        },
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        },
+    };
+    print!("{}", cache_summary(year));
+    ExitCode::SUCCESS
+}
+
+fn clean_cache(args: &[String]) -> ExitCode {
+    let mut scopes = Vec::new();
+    let mut year = None;
+    let mut day = None;
+    let mut dry_run = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--year" => match args.next().and_then(|arg| parse_year(arg)) {
+                Some(parsed) => year = Some(parsed),
+                None => {
+                    eprintln!("{}", "--year requires a valid year".red());
+                    return ExitCode::FAILURE;
+                },
+            },
+            "--day" => {
+                match args
+                    .next()
+                    .and_then(|arg| arg.parse().ok())
+                    .filter(|day| (1..=25).contains(day))
+                {
+                    Some(parsed) => day = Some(parsed),
+                    None => {
+                        eprintln!("{}", "--day requires a valid day".red());
+                        return ExitCode::FAILURE;
+                    },
+                }
+            },
+            "all" => scopes.extend(CacheScope::ALL),
+            scope => match scope.parse() {
+                Ok(scope) => scopes.push(scope),
+                Err(err) => {
+                    eprintln!("{}", err.red());
+                    return ExitCode::FAILURE;
+                },
+            },
+        }
+    }
+    if scopes.is_empty() {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+    clean(&scopes, year, day, dry_run);
+    ExitCode::SUCCESS
+}
+
+fn log(args: &[String]) -> ExitCode {
+    let mut filter = RequestFilter::default();
+    let mut list = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--list" => list = true,
+            "--today" => filter.date = Some(Utc::now().date_naive()),
+            "--date" => match args.next().and_then(|arg| arg.parse::<NaiveDate>().ok())
+            {
+                Some(date) => filter.date = Some(date),
+                None => {
+                    eprintln!("{}", "--date requires a date (YYYY-MM-DD)".red());
+                    return ExitCode::FAILURE;
+                },
+            },
+            "--year" => match args.next().and_then(|arg| parse_year(arg)) {
+                Some(year) => filter.year = Some(year),
+                None => {
+                    eprintln!("{}", "--year requires a valid year".red());
+                    return ExitCode::FAILURE;
+                },
+            },
+            "--day" => {
+                match args
+                    .next()
+                    .and_then(|arg| arg.parse().ok())
+                    .filter(|day| (1..=25).contains(day))
+                {
+                    Some(day) => filter.day = Some(day),
+                    None => {
+                        eprintln!("{}", "--day requires a valid day".red());
+                        return ExitCode::FAILURE;
+                    },
+                }
+            },
+            "--api" => match args.next() {
+                Some(api) => filter.api = Some(api.clone()),
+                None => {
+                    eprintln!("{}", "--api requires a function name".red());
+                    return ExitCode::FAILURE;
+                },
+            },
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::FAILURE;
+            },
+        }
+    }
+
+    let requests = query_requests(&filter);
+    if list {
+        for request in &requests {
+            println!("{request}");
+        }
+    }
+    let mut by_api = BTreeMap::new();
+    for request in &requests {
+        *by_api.entry(request.api.as_str()).or_insert(0) += 1;
+    }
+    for (api, count) in &by_api {
+        println!("{api}: {}", style(count).blue());
+    }
+    println!(
+        "{} {} {}",
+        "Total:".yellow(),
+        style(requests.len()).blue(),
+        if requests.len() == 1 {
+            "request"
+        } else {
+            "requests"
+        }
+        .yellow()
+    );
+    ExitCode::SUCCESS
+}
+
+fn timings(args: &[String]) -> ExitCode {
+    let mut year = None;
+    let mut threshold = regression_threshold();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threshold" => {
+                match args
+                    .next()
+                    .and_then(|arg| arg.trim_end_matches('%').parse::<f64>().ok())
+                    .filter(|threshold| threshold.is_finite() && *threshold >= 0.0)
+                {
+                    Some(parsed) => threshold = parsed,
+                    None => {
+                        eprintln!("{}", "--threshold requires a percentage".red());
+                        return ExitCode::FAILURE;
+                    },
+                }
+            },
+            arg => match parse_year(arg) {
+                Some(parsed) => year = Some(parsed),
+                None => {
+                    eprintln!("{} {arg}", "Invalid year:".red());
+                    return ExitCode::FAILURE;
+                },
+            },
+        }
+    }
+    let report = timing_report(year, threshold);
+    if report.parts.is_empty() {
+        println!("{}", "No runs have been timed yet.".yellow());
+        return ExitCode::SUCCESS;
+    }
+    print!("{report}");
+    if report.regressions().next().is_some() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn flush(args: &[String]) -> ExitCode {
+    let queued = queued_answers();
+    match args {
+        [] => {},
+        [list] if list == "--list" => {
+            for answer in &queued {
+                println!("{answer}");
+            }
+        },
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        },
+    }
+    if queued.is_empty() {
+        println!("{}", "No answers are queued.".green());
+        return ExitCode::SUCCESS;
+    }
+    let remaining = if args.is_empty() {
+        sync_flush_submissions().len()
+    } else {
+        queued.len()
+    };
+    if remaining == 0 {
+        println!("{}", "Every queued answer has been submitted.".green());
+        return ExitCode::SUCCESS;
+    }
+    println!(
+        "{} {} {}",
+        style(remaining).blue(),
+        if remaining == 1 {
+            "answer is"
+        } else {
+            "answers are"
+        }
+        .yellow(),
+        "still queued.".yellow()
+    );
+    if args.is_empty() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn validate() -> ExitCode {
+    for change in migrate_cache() {
+        println!("{} {change}", "Migrated cache:".yellow());
+    }
+    let issues = validate_cache();
+    if issues.is_empty() {
+        println!("{}", "No problems found.".green());
+        return ExitCode::SUCCESS;
+    }
+    for issue in &issues {
+        println!("{} {issue}", "Problem:".red());
+    }
+    println!(
+        "{} {} {}",
+        "Found".red(),
+        style(issues.len()).blue(),
+        if issues.len() == 1 {
+            "problem."
+        } else {
+            "problems."
+        }
+        .red()
+    );
+    ExitCode::FAILURE
+}
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("stars") => stars(&args[1..]),
+        Some("fetch") => fetch(&args[1..]),
+        Some("summary") => summary(&args[1..]),
+        Some("clean") => clean_cache(&args[1..]),
+        Some("log") => log(&args[1..]),
+        Some("flush") => flush(&args[1..]),
+        Some("timings") => timings(&args[1..]),
+        Some("validate") => validate(),
+        Some("help" | "--help" | "-h") => {
+            println!("{USAGE}");
+            ExitCode::SUCCESS
+        },
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::FAILURE
+        },
+    }
+}
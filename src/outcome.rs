@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+/// How running a part's solution with
+/// [`sync_lazy_submit_part`](crate::sync_lazy_submit_part) (or its async
+/// equivalent) went.
+///
+/// These functions returned `()` before they reported outcomes, so callers
+/// which ignore the result now need to discard it explicitly (`let _ = ...;`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use = "a part may have panicked or timed out"]
+pub enum PartOutcome {
+    /// The solution finished, or didn't need to be run.
+    Finished,
+    /// The solution (or the input parser) panicked.
+    Panicked {
+        /// The panic message, and where the panic happened if known.
+        message: String,
+        /// How long the solution had been running for when it panicked.
+        elapsed: Duration,
+    },
+    /// The solution was abandoned after running for the time limit.
+    TimedOut(Duration),
+}
+
+impl PartOutcome {
+    /// Whether the solution panicked or timed out.
+    #[must_use]
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, Self::Finished)
+    }
+}
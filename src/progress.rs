@@ -0,0 +1,100 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crossterm::style::Stylize;
+
+/// How many steps the running solution has completed.
+static DONE: AtomicU64 = AtomicU64::new(0);
+/// How many steps the running solution has in total; 0 if it hasn't reported
+/// any progress.
+static TOTAL: AtomicU64 = AtomicU64::new(0);
+
+/// Report how far through its work the solution running under
+/// [`sync_work`](crate::sync_work) (or [`async_work`](crate::async_work)) is,
+/// so that the spinner can show a percentage, rate and ETA.
+///
+/// This can be called from any thread. To count steps completed by several
+/// threads at once, use a [`ProgressHandle`] instead.
+pub fn progress(done: u64, total: u64) {
+    DONE.store(done, Ordering::Relaxed);
+    TOTAL.store(total, Ordering::Relaxed);
+}
+
+/// A handle for reporting the progress of the running solution, which can be
+/// shared between threads (e.g. `rayon` workers) to count the steps each of
+/// them completes.
+///
+/// Progress is shown by the spinner of [`sync_work`](crate::sync_work) (or
+/// [`async_work`](crate::async_work)), and reset when it finishes.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressHandle {
+    _private: (),
+}
+
+impl ProgressHandle {
+    /// Start counting progress towards `total` steps, from 0.
+    #[must_use]
+    pub fn new(total: u64) -> Self {
+        progress(0, total);
+        Self { _private: () }
+    }
+
+    /// Record that `steps` more steps have been completed.
+    pub fn inc(&self, steps: u64) {
+        DONE.fetch_add(steps, Ordering::Relaxed);
+    }
+
+    /// Record that `done` steps have been completed in total.
+    pub fn set(&self, done: u64) {
+        DONE.store(done, Ordering::Relaxed);
+    }
+
+    /// Change the total number of steps, if it turns out to be different.
+    pub fn set_total(&self, total: u64) {
+        TOTAL.store(total, Ordering::Relaxed);
+    }
+}
+
+/// Forget the progress of the last solution, once it has finished.
+pub(crate) fn reset_progress() {
+    progress(0, 0);
+}
+
+/// Format a number of steps per second, with an SI prefix.
+fn format_rate(rate: f64) -> String {
+    match rate {
+        rate if rate >= 1e9 => format!("{:.1}G/s", rate / 1e9),
+        rate if rate >= 1e6 => format!("{:.1}M/s", rate / 1e6),
+        rate if rate >= 1e3 => format!("{:.1}K/s", rate / 1e3),
+        rate => format!("{rate:.1}/s"),
+    }
+}
+
+/// Describe the progress of the running solution, if it has reported any,
+/// given that it has been running for `elapsed`.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub(crate) fn progress_status(elapsed: Duration) -> Option<String> {
+    let total = TOTAL.load(Ordering::Relaxed);
+    if total == 0 {
+        return None;
+    }
+    let done = DONE.load(Ordering::Relaxed).min(total);
+    let rate = done as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+    let eta = if rate > 0.0 {
+        let eta = ((total - done) as f64 / rate) as u64;
+        format!("{:02}:{:02}:{:02}", eta / 3600, eta / 60 % 60, eta % 60)
+    } else {
+        "--:--:--".into()
+    };
+    Some(format!(
+        "{} {} {}{}",
+        format!("{:>5.1}%", done as f64 / total as f64 * 100.0).blue(),
+        format!("{:>9}", format_rate(rate)).blue(),
+        "ETA ".yellow(),
+        eta.yellow(),
+    ))
+}
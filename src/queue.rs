@@ -0,0 +1,115 @@
+use std::fmt::{self, Display};
+use std::fs;
+
+use chrono::{DateTime, Utc};
+use crossterm::style::{style, Stylize};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{write_atomic, CacheLock};
+use crate::data::DATA_DIR;
+
+/// Answers which couldn't be delivered to Advent of Code, oldest first.
+const QUEUE_FILE: &str = "submission_queue.json";
+
+/// An answer waiting to be submitted, because Advent of Code couldn't be
+/// reached when it was first submitted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedAnswer {
+    /// The year of the puzzle.
+    pub year: i32,
+    /// The day of the puzzle.
+    pub day: u32,
+    /// The part of the puzzle (day 25 part 2 has no answer, and finishes the
+    /// event).
+    pub part: u32,
+    /// The answer to submit.
+    pub answer: String,
+    /// When the answer was queued, in seconds since the Unix epoch.
+    pub queued_at: i64,
+}
+
+impl QueuedAnswer {
+    fn is_for(&self, year: i32, day: u32, part: u32, answer: &str) -> bool {
+        self.year == year
+            && self.day == day
+            && self.part == part
+            && self.answer == answer
+    }
+}
+
+impl Display for QueuedAnswer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} day {} part {}: {} (queued {})",
+            style(self.year).blue(),
+            style(self.day).blue(),
+            style(self.part).blue(),
+            self.answer.as_str().blue(),
+            DateTime::from_timestamp(self.queued_at, 0)
+                .unwrap_or_default()
+                .format("%Y-%m-%d %H:%M:%S"),
+        )
+    }
+}
+
+/// Run `update` on the queue, with every other process locked out.
+fn with_queue<T>(update: impl FnOnce(&mut Vec<QueuedAnswer>) -> T) -> T {
+    let file = &*DATA_DIR / QUEUE_FILE;
+    let _lock = CacheLock::acquire(&file);
+    let mut queue = read_queue();
+    let result = update(&mut queue);
+    write_atomic(
+        &file,
+        serde_json::to_string(&queue).expect("Serialising should never fail"),
+    )
+    .expect("Writing the submission queue failed. Check your permissions.");
+    result
+}
+
+fn read_queue() -> Vec<QueuedAnswer> {
+    let Ok(data) = fs::read_to_string(&*DATA_DIR / QUEUE_FILE) else {
+        return Vec::new();
+    };
+    serde_json::from_str(&data).expect("Failed to parse the submission queue.")
+}
+
+/// Add an answer to the queue, unless it's already there.
+pub(crate) fn enqueue(year: i32, day: u32, part: u32, answer: &str) {
+    with_queue(|queue| {
+        if !queue
+            .iter()
+            .any(|queued| queued.is_for(year, day, part, answer))
+        {
+            queue.push(QueuedAnswer {
+                year,
+                day,
+                part,
+                answer: answer.into(),
+                queued_at: Utc::now().timestamp(),
+            });
+        }
+    });
+    eprintln!(
+        "{} {}",
+        answer.blue(),
+        "has been queued. Run `raoc flush` to submit it once Advent of Code can be \
+         reached."
+            .yellow()
+    );
+}
+
+/// Remove an answer from the queue, once it has been delivered.
+pub(crate) fn dequeue(queued: &QueuedAnswer) {
+    with_queue(|queue| {
+        queue.retain(|other| {
+            !other.is_for(queued.year, queued.day, queued.part, &queued.answer)
+        });
+    });
+}
+
+/// Every answer waiting to be submitted, oldest first.
+#[must_use]
+pub fn queued_answers() -> Vec<QueuedAnswer> {
+    read_queue()
+}
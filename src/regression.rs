@@ -0,0 +1,83 @@
+use std::fs;
+
+use crate::cache::{input_file, solution_file};
+use crate::MaybeDisplay;
+
+/// Generate regression tests for a solved puzzle, which run each part's
+/// solution on the cached input and assert that it still produces the
+/// accepted answer.
+///
+/// The arguments are the same as [`sync_lazy_submit`](crate::sync_lazy_submit)
+/// takes. This expands to two `#[test]` functions, `regression_part_1` and
+/// `regression_part_2`, so invoke it at most once per module:
+///
+/// ```ignore
+/// #[cfg(test)]
+/// mod tests {
+///     use super::*;
+///
+///     raoc::regression_test!(1, 2023, part_1, part_2, parse);
+/// }
+/// ```
+///
+/// If the input or the accepted answer isn't cached, the test fails, saying
+/// which is missing; run the solution normally first to cache them. Nothing is
+/// ever requested from Advent of Code. Part 2 of day 25 has no answer, so its
+/// test always passes.
+#[macro_export]
+macro_rules! regression_test {
+    ($day:expr, $year:expr, $part_1:expr, $part_2:expr, $parse_raw:expr $(,)?) => {
+        #[test]
+        fn regression_part_1() {
+            $crate::run_regression_test($day, $year, 1, $part_1, $parse_raw);
+        }
+
+        #[test]
+        fn regression_part_2() {
+            $crate::run_regression_test($day, $year, 2, $part_2, $parse_raw);
+        }
+    };
+}
+
+/// The implementation of [`regression_test!`]: run `solution` on the cached
+/// input, and assert that it produces the accepted answer for `part`.
+///
+/// # Panics
+///
+/// If the input or the accepted answer isn't cached, or the answer doesn't
+/// match.
+#[doc(hidden)]
+pub fn run_regression_test<U, M: MaybeDisplay>(
+    day: u32,
+    year: i32,
+    part: u32,
+    solution: impl FnOnce(U) -> M,
+    parse_raw: impl FnOnce(&str) -> U,
+) {
+    if day == 25 && part == 2 {
+        // there is no answer to check
+        return;
+    }
+    let Ok(expected) = fs::read_to_string(solution_file(year, day, part)) else {
+        panic!(
+            "No accepted answer is cached for day {day} of {year}, part {part}; \
+             submit one by running the solution before regression testing it."
+        );
+    };
+    let Ok(raw) = fs::read_to_string(input_file(year, day)) else {
+        panic!(
+            "The input for day {day} of {year} isn't cached; fetch it by running the \
+             solution before regression testing it."
+        );
+    };
+    #[cfg(feature = "pretty_assertions")]
+    use pretty_assertions::assert_eq;
+    let answer = solution(parse_raw(&raw))
+        .into_solution()
+        .expect("The solution should return an answer");
+    assert_eq!(
+        answer, expected,
+        "The accepted answer for day {day} of {year}, part {part} was {expected} but \
+         your answer was {answer}.",
+    );
+}
@@ -0,0 +1,345 @@
+use std::fmt::{self, Display};
+
+use crossterm::style::Stylize;
+use serde::{Deserialize, Serialize};
+
+use crate::internal_util::format_time;
+
+/// Your personal statistics for a year, as shown on your personal leaderboard
+/// page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonalStats {
+    /// The year these statistics are for.
+    pub year: i32,
+    /// Your statistics for each day you have collected at least one star on,
+    /// in ascending order of day.
+    pub days: Vec<DayStats>,
+}
+
+/// Your statistics for a single day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayStats {
+    /// The day these statistics are for.
+    pub day: u32,
+    /// Your statistics for part 1, if you have solved it.
+    pub part_1: Option<PartStats>,
+    /// Your statistics for part 2, if you have solved it.
+    pub part_2: Option<PartStats>,
+}
+
+/// Your statistics for a single part of a puzzle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartStats {
+    /// The time, in seconds, from puzzle unlock to your solve, or `None` if it
+    /// took more than 24 hours.
+    pub time: Option<u32>,
+    /// Your rank among everyone who solved this part.
+    pub rank: u32,
+    /// The global leaderboard score you received for this part.
+    pub score: u32,
+}
+
+impl PersonalStats {
+    /// Parse the contents of the `<pre>` block on the personal leaderboard
+    /// page.
+    pub(crate) fn parse(year: i32, table: &str) -> Self {
+        let mut days = table
+            .lines()
+            .filter_map(|line| {
+                let fields = line.split_whitespace().collect::<Vec<_>>();
+                let day = fields.first()?.parse().ok()?;
+                Some(DayStats {
+                    day,
+                    part_1: fields.get(1..4).and_then(PartStats::parse),
+                    part_2: fields.get(4..7).and_then(PartStats::parse),
+                })
+            })
+            .collect::<Vec<_>>();
+        days.sort_by_key(|day| day.day);
+        Self { year, days }
+    }
+
+    /// Your statistics for `day`, if you have collected a star on it.
+    #[must_use]
+    pub fn day(&self, day: u32) -> Option<&DayStats> {
+        self.days.iter().find(|stats| stats.day == day)
+    }
+}
+
+impl DayStats {
+    /// Your statistics for `part`, if you have solved it.
+    ///
+    /// # Panics
+    ///
+    /// If `part` is neither 1 nor 2.
+    #[must_use]
+    pub fn part(&self, part: u32) -> Option<&PartStats> {
+        match part {
+            1 => self.part_1.as_ref(),
+            2 => self.part_2.as_ref(),
+            _ => panic!("part was neither 1 nor 2"),
+        }
+    }
+}
+
+impl PartStats {
+    fn parse(fields: &[&str]) -> Option<Self> {
+        let [time, rank, score] = fields else {
+            return None;
+        };
+        let time = if time.ends_with("24h") {
+            None
+        } else {
+            let mut parts = time.split(':').map(str::parse::<u32>);
+            let (Some(Ok(hours)), Some(Ok(minutes)), Some(Ok(seconds))) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                return None;
+            };
+            Some(hours * 3600 + minutes * 60 + seconds)
+        };
+        Some(Self {
+            time,
+            rank: rank.parse().ok()?,
+            score: score.parse().ok()?,
+        })
+    }
+}
+
+/// Global completion statistics for a year, as shown on the event's stats
+/// page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalStats {
+    /// The year these statistics are for.
+    pub year: i32,
+    /// The completion statistics for each unlocked day, in ascending order of
+    /// day.
+    pub days: Vec<GlobalDayStats>,
+}
+
+/// Global completion statistics for a single day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalDayStats {
+    /// The day these statistics are for.
+    pub day: u32,
+    /// The number of users who have solved both parts.
+    pub gold: u32,
+    /// The number of users who have solved only part 1.
+    pub silver: u32,
+}
+
+impl GlobalStats {
+    /// Parse the contents of the `<pre>` block on the stats page.
+    pub(crate) fn parse(year: i32, table: &str) -> Self {
+        let mut days = table
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace().map(str::parse);
+                Some(GlobalDayStats {
+                    day: fields.next()?.ok()?,
+                    gold: fields.next()?.ok()?,
+                    silver: fields.next()?.ok()?,
+                })
+            })
+            .collect::<Vec<_>>();
+        days.sort_by_key(|day| day.day);
+        Self { year, days }
+    }
+
+    /// The number of users who have solved `part` of `day`, if the day has
+    /// unlocked.
+    ///
+    /// # Panics
+    ///
+    /// If `part` is neither 1 nor 2.
+    #[must_use]
+    pub fn solvers(&self, day: u32, part: u32) -> Option<u32> {
+        let stats = self.days.iter().find(|stats| stats.day == day)?;
+        match part {
+            1 => Some(stats.gold + stats.silver),
+            2 => Some(stats.gold),
+            _ => panic!("part was neither 1 nor 2"),
+        }
+    }
+
+    /// The percentage of solvers of `part` of `day` who solved it at least as
+    /// quickly as whoever achieved `rank`.
+    ///
+    /// Lower is better; rank 1 of 1000 solvers is the top 0.1%.
+    ///
+    /// # Panics
+    ///
+    /// If `part` is neither 1 nor 2.
+    #[must_use]
+    pub fn percentile(&self, day: u32, part: u32, rank: f64) -> Option<f64> {
+        let solvers = self.solvers(day, part)?;
+        if solvers == 0 {
+            return None;
+        }
+        Some((rank / f64::from(solvers) * 100.0).min(100.0))
+    }
+}
+
+impl PersonalStats {
+    /// Display these statistics alongside the percentile each solve placed you
+    /// in, according to `global`.
+    #[must_use]
+    pub fn with_percentiles<'a>(
+        &'a self,
+        global: &'a GlobalStats,
+    ) -> impl Display + 'a {
+        PersonalStatsTable {
+            stats: self,
+            global: Some(global),
+        }
+    }
+}
+
+struct PersonalStatsTable<'a> {
+    stats: &'a PersonalStats,
+    global: Option<&'a GlobalStats>,
+}
+
+impl Display for PersonalStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        PersonalStatsTable {
+            stats: self,
+            global: None,
+        }
+        .fmt(f)
+    }
+}
+
+impl Display for PersonalStatsTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}",
+            format!("Personal statistics for {}", self.stats.year).yellow()
+        )?;
+        write!(f, "{:>3}", "Day")?;
+        for _ in 0..2 {
+            write!(f, "  {:>8} {:>7} {:>5}", "Time", "Rank", "Score")?;
+            if self.global.is_some() {
+                write!(f, " {:>7}", "Top")?;
+            }
+        }
+        writeln!(f)?;
+        for day in self.stats.days.iter().rev() {
+            write!(f, "{:>3}", day.day)?;
+            for (part, stats) in [(1, &day.part_1), (2, &day.part_2)] {
+                match stats {
+                    Some(stats) => write!(
+                        f,
+                        "  {:>8} {:>7} {:>5}",
+                        stats.time.map_or_else(
+                            || ">24h".into(),
+                            |time| format_time(time.into())
+                        ),
+                        stats.rank,
+                        stats.score,
+                    )?,
+                    None => write!(f, "  {:>8} {:>7} {:>5}", '-', '-', '-')?,
+                }
+                if let Some(global) = self.global {
+                    match stats.as_ref().and_then(|stats| {
+                        global.percentile(day.day, part, stats.rank.into())
+                    }) {
+                        Some(percentile) => write!(f, " {:>6.2}%", percentile)?,
+                        None => write!(f, " {:>7}", '-')?,
+                    }
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GlobalStats, PersonalStats};
+    use crate::internal_util::pre_from_body;
+
+    /// The table on a personal leaderboard page, part-way through an event.
+    const PERSONAL_PAGE: &str = r#"<main><article><p>These are your personal leaderboard statistics.</p>
+<pre>      <span class="leaderboard-daydesc-first">--------Part 1--------</span>   <span class="leaderboard-daydesc-both">--------Part 2---------</span>
+Day   <span class="leaderboard-daydesc-first">    Time   Rank  Score</span>   <span class="leaderboard-daydesc-both">    Time    Rank  Score</span>
+  3   00:14:02    812      0          -       -      -
+  2   00:05:11     61     40   00:07:46      98      3
+  1       &gt;24h  91234      0       &gt;24h   80000      0
+</pre></article></main>"#;
+
+    /// The table on an event's stats page.
+    const GLOBAL_PAGE: &str = r#"<main><article><p>Gold indicates users who have completed both parts of a puzzle.</p>
+<pre class="stats"><a href="/2023/day/2"> 2 <span class="stats-both">  2210</span> <span class="stats-firstonly">   154</span>  <span class="stats-both">**********</span></a>
+<a href="/2023/day/1"> 1 <span class="stats-both">304213</span> <span class="stats-firstonly"> 73419</span>  <span class="stats-both">**********</span><span class="stats-firstonly">***</span></a>
+</pre></article></main>"#;
+
+    #[test]
+    fn personal_stats_are_parsed() {
+        let table = pre_from_body(PERSONAL_PAGE).expect("The fixture has a table");
+        let stats = PersonalStats::parse(2023, &table);
+        assert_eq!(stats.year, 2023);
+        assert_eq!(
+            stats.days.iter().map(|day| day.day).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+
+        let day_2 = stats.day(2).expect("Day 2 was solved");
+        let part_1 = day_2.part(1).expect("Part 1 was solved");
+        assert_eq!(
+            (part_1.time, part_1.rank, part_1.score),
+            (Some(5 * 60 + 11), 61, 40)
+        );
+        let part_2 = day_2.part(2).expect("Part 2 was solved");
+        assert_eq!(
+            (part_2.time, part_2.rank, part_2.score),
+            (Some(7 * 60 + 46), 98, 3)
+        );
+    }
+
+    #[test]
+    fn slow_and_unsolved_parts_are_parsed() {
+        let table = pre_from_body(PERSONAL_PAGE).expect("The fixture has a table");
+        let stats = PersonalStats::parse(2023, &table);
+        let day_1 = stats.day(1).expect("Day 1 was solved");
+        assert_eq!(day_1.part(1).map(|part| part.time), Some(None));
+        assert_eq!(day_1.part(2).map(|part| part.rank), Some(80000));
+        let day_3 = stats.day(3).expect("Day 3 was solved");
+        assert!(day_3.part(1).is_some());
+        assert!(day_3.part(2).is_none());
+        assert!(stats.day(4).is_none());
+    }
+
+    #[test]
+    fn personal_stats_can_be_empty() {
+        // no stars have been collected, so there's no table at all
+        assert!(PersonalStats::parse(2023, "").days.is_empty());
+    }
+
+    #[test]
+    fn global_stats_are_parsed() {
+        let table = pre_from_body(GLOBAL_PAGE).expect("The fixture has a table");
+        let stats = GlobalStats::parse(2023, &table);
+        assert_eq!(
+            stats
+                .days
+                .iter()
+                .map(|day| (day.day, day.gold, day.silver))
+                .collect::<Vec<_>>(),
+            [(1, 304_213, 73419), (2, 2210, 154)]
+        );
+        assert_eq!(stats.solvers(1, 1), Some(304_213 + 73419));
+        assert_eq!(stats.solvers(1, 2), Some(304_213));
+        assert_eq!(stats.solvers(3, 1), None);
+        assert_eq!(stats.percentile(2, 2, 221.0), Some(10.0));
+    }
+
+    #[test]
+    fn global_stats_can_be_empty() {
+        let stats = GlobalStats::parse(2023, "");
+        assert!(stats.days.is_empty());
+        assert_eq!(stats.percentile(1, 1, 1.0), None);
+    }
+}
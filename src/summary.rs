@@ -0,0 +1,246 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+use std::fs;
+
+use crossterm::style::Stylize;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{
+    input_file,
+    numbered_dirs,
+    practice_dir,
+    read_submissions,
+    solution_file,
+    submissions_file,
+    year_dir,
+};
+use crate::data::{DATA_DIR, PRACTICE_DATA_DIR};
+use crate::internal_util::{format_time, Submissions};
+
+/// Everything cached in the data directory, summarised per puzzle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheSummary {
+    /// The summary of each puzzle with any cached data, ordered by year and
+    /// day.
+    pub puzzles: Vec<PuzzleSummary>,
+}
+
+/// The cached data for a single puzzle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PuzzleSummary {
+    /// The year of the puzzle.
+    pub year: i32,
+    /// The day of the puzzle.
+    pub day: u32,
+    /// Whether the puzzle input has been cached.
+    pub input_cached: bool,
+    /// The cached data for part 1.
+    pub part_1: PartSummary,
+    /// The cached data for part 2.
+    pub part_2: PartSummary,
+    /// The practice runs recorded for this puzzle.
+    pub practice: Vec<PracticeAttempt>,
+}
+
+/// The cached data for a single part of a puzzle.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PartSummary {
+    /// The accepted answer, if the part has been solved.
+    pub answer: Option<String>,
+    /// The number of submitted answers that were rejected.
+    pub wrong_submissions: usize,
+    /// The largest answer that was rejected for being too low.
+    pub lower_bound: Option<i128>,
+    /// The smallest answer that was rejected for being too high.
+    pub upper_bound: Option<i128>,
+}
+
+/// A day's practice run of a puzzle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PracticeAttempt {
+    /// The date of the run, as `YYYY-MM-DD`.
+    pub date: String,
+    /// The time, in seconds after unlock, at which each part was solved.
+    pub solve_times: Vec<f64>,
+}
+
+impl PartSummary {
+    fn load(year: i32, day: u32, part: u32, submissions: Option<&Submissions>) -> Self {
+        let mut summary = Self {
+            answer: fs::read_to_string(solution_file(year, day, part))
+                .ok()
+                .map(|answer| answer.trim().to_string()),
+            ..Self::default()
+        };
+        let Some(submissions) = submissions else {
+            return summary;
+        };
+        let responses = match part {
+            1 => &submissions.part_1,
+            2 => &submissions.part_2,
+            _ => unreachable!("Part should be 1 or 2"),
+        };
+        for (answer, response) in responses {
+            if !response.starts_with("That's not") {
+                continue;
+            }
+            summary.wrong_submissions += 1;
+            let Ok(answer) = answer.trim().parse::<i128>() else {
+                continue;
+            };
+            if response.contains("too low") {
+                summary.lower_bound = summary.lower_bound.max(Some(answer));
+            } else if response.contains("too high") {
+                summary.upper_bound = Some(
+                    summary
+                        .upper_bound
+                        .map_or(answer, |bound| bound.min(answer)),
+                );
+            }
+        }
+        summary
+    }
+
+    fn bounds(&self) -> String {
+        match (self.lower_bound, self.upper_bound) {
+            (None, None) => String::new(),
+            (Some(low), None) => format!("> {low}"),
+            (None, Some(high)) => format!("< {high}"),
+            (Some(low), Some(high)) => format!("{low} < x < {high}"),
+        }
+    }
+}
+
+impl PuzzleSummary {
+    /// Summarise the cached data for `day` of `year`.
+    #[must_use]
+    pub fn load(year: i32, day: u32) -> Self {
+        let submissions = read_submissions(&submissions_file(year, day));
+
+        let mut practice = fs::read_dir(practice_dir(year, day))
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .filter_map(|entry| {
+                        let path = entry.path();
+                        let date = path.file_stem()?.to_str()?.to_string();
+                        let solve_times =
+                            serde_json::from_str(&fs::read_to_string(&path).ok()?)
+                                .ok()?;
+                        Some(PracticeAttempt { date, solve_times })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        practice.sort_by(|a, b| a.date.cmp(&b.date));
+
+        Self {
+            year,
+            day,
+            input_cached: input_file(year, day).exists(),
+            part_1: PartSummary::load(year, day, 1, submissions.as_ref()),
+            part_2: PartSummary::load(year, day, 2, submissions.as_ref()),
+            practice,
+        }
+    }
+}
+
+/// Summarise everything cached in the data directory, without making any
+/// requests.
+///
+/// If `year` is given, only puzzles from that year are included.
+#[must_use]
+pub fn cache_summary(year: Option<i32>) -> CacheSummary {
+    let years = match year {
+        Some(year) => BTreeSet::from([year]),
+        None => [&*DATA_DIR, &*PRACTICE_DATA_DIR]
+            .into_iter()
+            .flat_map(numbered_dirs)
+            .filter_map(|(year, _)| i32::try_from(year).ok())
+            .collect(),
+    };
+    let puzzles = years
+        .into_iter()
+        .filter(|&year| year >= 2015)
+        .flat_map(|year| {
+            // inputs are cached beside the day directories, not inside them
+            let days = [year_dir(year), &*PRACTICE_DATA_DIR / year.to_string()]
+                .iter()
+                .flat_map(numbered_dirs)
+                .filter_map(|(day, _)| u32::try_from(day).ok())
+                .chain((1..=25).filter(|&day| input_file(year, day).exists()))
+                .filter(|day| (1..=25).contains(day))
+                .collect::<BTreeSet<_>>();
+            days.into_iter()
+                .map(move |day| PuzzleSummary::load(year, day))
+        })
+        .collect();
+    CacheSummary { puzzles }
+}
+
+impl Display for CacheSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}",
+            format!(
+                "{:>4} {:>3} {:<5}  {:<20} {:>5} {:<20}  {:<20} {:>5} {:<20}  {}",
+                "Year",
+                "Day",
+                "Input",
+                "Part 1",
+                "Wrong",
+                "Bounds",
+                "Part 2",
+                "Wrong",
+                "Bounds",
+                "Best practice",
+            )
+            .yellow()
+        )?;
+        for puzzle in &self.puzzles {
+            write!(
+                f,
+                "{:>4} {:>3} {:<5}",
+                puzzle.year,
+                puzzle.day,
+                if puzzle.input_cached { "yes" } else { "no" },
+            )?;
+            for part in [&puzzle.part_1, &puzzle.part_2] {
+                let answer = part.answer.as_deref().unwrap_or("-");
+                write!(
+                    f,
+                    "  {} {:>5} {:<20}",
+                    if part.answer.is_some() {
+                        format!("{answer:<20}").green()
+                    } else {
+                        format!("{answer:<20}").dark_grey()
+                    },
+                    part.wrong_submissions,
+                    part.bounds(),
+                )?;
+            }
+            // only attempts which solved every part are comparable
+            let parts = if puzzle.day == 25 { 1 } else { 2 };
+            let best_practice = puzzle
+                .practice
+                .iter()
+                .filter(|attempt| attempt.solve_times.len() == parts)
+                .filter_map(|attempt| attempt.solve_times.last())
+                .copied()
+                .reduce(f64::min);
+            match best_practice {
+                Some(time) => write!(
+                    f,
+                    "  {} ({} run{})",
+                    format_time(time),
+                    puzzle.practice.len(),
+                    if puzzle.practice.len() == 1 { "" } else { "s" },
+                )?,
+                None => write!(f, "  -")?,
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -1,13 +1,18 @@
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::fs;
-use std::time::Duration;
+use std::hint::black_box;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 use chrono::{DateTime, Datelike, TimeZone, Utc};
 use crossterm::style::{style, Stylize};
+use reqwest::StatusCode;
 
 use super::internal_util::{
     calculate_practice_result,
+    check_answer_accepted,
     get,
     load_token_from_stdin,
     make,
@@ -15,19 +20,94 @@ use super::internal_util::{
     practice_result_for,
     wait,
     work,
+    work_with_time_limit,
+};
+use crate::cache::{
+    day_dir,
+    input_file,
+    input_metadata_file,
+    private_leaderboard_file,
+    read_submissions,
+    solution_file,
+    submissions_file,
+    verify_input,
+    write_atomic,
+    year_dir,
+    CacheLock,
+    InputMetadata,
+    CALENDAR,
+    GLOBAL_STATS,
+    PERSONAL_STATS,
+    PRIVATE_LEADERBOARDS,
+    SUBMISSIONS,
+};
+use crate::data::{
+    base_url,
+    calendar_url,
+    global_stats_url,
+    personal_stats_url,
+    private_leaderboard_url,
+    LEADERBOARD_REFRESH,
+    MAX_RETRIES,
+    TOKEN_FILE,
+    WAIT_TIME,
 };
-use crate::data::{base_url, DATA_DIR, WAIT_TIME};
 use crate::internal_util::{
+    catch_panic,
+    check_input,
+    finished_run,
+    is_bench_mode,
+    is_fresh,
+    is_logged_out,
+    is_offline,
     is_practice_mode,
+    is_verify_mode,
     message_from_body,
     must_run_solutions,
     open_page,
+    pre_from_body,
     pretty_print,
     print_rank,
+    read_stale,
+    report_skipped,
+    report_verification,
+    skip_bench,
     strip_trailing_nl,
+    time_limit,
+    warn_stale,
+    Benchmark,
+    InputProblem,
+    SendError,
     Submissions,
 };
-use crate::MaybeDisplay;
+use crate::queue::{dequeue, enqueue, queued_answers, QueuedAnswer};
+use crate::timings::{print_run_timings, record_run, Stage, Stopwatch};
+use crate::{
+    Calendar,
+    GlobalStats,
+    MaybeDisplay,
+    PartOutcome,
+    PersonalStats,
+    PrivateLeaderboard,
+};
+
+/// Cache a freshly-fetched input, along with its metadata.
+fn cache_input(in_file: &Path, input: &str) {
+    write_atomic(in_file, input)
+        .and_then(|()| {
+            write_atomic(
+                &input_metadata_file(in_file),
+                InputMetadata::new(input, SystemTime::now()).to_json(),
+            )
+        })
+        .unwrap_or_else(|_| {
+            eprintln!(
+                "{}",
+                "Warning: Failed to cache input file. Please check your permissions."
+                    .red()
+            );
+        });
+}
 
 /// Fetch and return the input for `day` of `year`.
 ///
@@ -46,9 +126,8 @@ use crate::MaybeDisplay;
 pub fn fetch(day: u32, year: i32, never_print: bool) -> String {
     assert!(year >= 2015, "Invalid year");
     assert!((1..=25).contains(&day), "Invalid day");
-    let in_folder = &*DATA_DIR / year.to_string();
-    make(&in_folder);
-    let in_file = &in_folder / format!("{day}.in");
+    make(&year_dir(year));
+    let in_file = input_file(year, day);
 
     let wait_for_unlock = |now: DateTime<Utc>, unlock: DateTime<Utc>| {
         if now < unlock {
@@ -77,15 +156,39 @@ pub fn fetch(day: u32, year: i32, never_print: bool) -> String {
         } else {
             false
         };
-        let input = fs::read_to_string(&in_file).unwrap_or_else(|_| {
-            fs::remove_file(in_file).expect("Removing the input file should not fail");
-            fetch(day, year, true)
-        });
+        let input = fs::read_to_string(&in_file)
+            .map_err(|_| "the input could not be read".to_string())
+            .and_then(|input| verify_input(&in_file, &input).map(|()| input))
+            .unwrap_or_else(|problem| {
+                eprintln!(
+                    "{} {problem}. {}",
+                    "Warning: The cached input is invalid:".red(),
+                    "Fetching it again...".red()
+                );
+                fs::remove_file(&in_file)
+                    .expect("Removing the input file should not fail");
+                fetch(day, year, true)
+            });
+        if !input_metadata_file(&in_file).exists() {
+            // cached before metadata was recorded; the best guess at when it
+            // was fetched is when it was written
+            let fetched_at = fs::metadata(&in_file)
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or_else(|_| SystemTime::now());
+            let _ = write_atomic(
+                &input_metadata_file(&in_file),
+                InputMetadata::new(&input, fetched_at).to_json(),
+            );
+        }
         if should_print && !never_print && is_practice_mode() {
             println!("{input}");
         }
         input
     } else {
+        assert!(
+            !is_offline(),
+            "The input for day {day} of {year} isn't cached, and raoc is offline."
+        );
         let mut unlock = Utc
             .with_ymd_and_hms(year, 12, day, 5, 0, 0)
             .single()
@@ -100,8 +203,14 @@ pub fn fetch(day: u32, year: i32, never_print: bool) -> String {
         if now < unlock {
             // On the first day, run a stray request to validate the user's token
             if day == 1 {
-                let resp = get(&(base_url(year, day) + "/input"), true);
-                if resp.status().is_client_error() {
+                let resp = get(&(base_url(year, day) + "/input"), true, "fetch")
+                    .unwrap_or_else(|err| {
+                        panic!(
+                            "Couldn't fetch the input for day {day} of {year}, as \
+                             {err}."
+                        )
+                    });
+                if resp.status.is_client_error() {
                     load_token_from_stdin(
                         "Your token has expired. Please enter your new token.".red(),
                     );
@@ -111,32 +220,167 @@ pub fn fetch(day: u32, year: i32, never_print: bool) -> String {
             }
             wait_for_unlock(now, unlock);
         }
-        let resp = get(&(base_url(year, day) + "/input"), true);
-        if !resp.status().is_success() {
-            if resp.status().is_client_error() {
-                load_token_from_stdin(
-                    "Your token has expired. Please enter your new token.".red(),
+        let resp = get(&(base_url(year, day) + "/input"), true, "fetch")
+            .unwrap_or_else(|err| {
+                panic!("Couldn't fetch the input for day {day} of {year}, as {err}.")
+            });
+        let status = resp.status;
+        let input = strip_trailing_nl(resp.body);
+        let problem = check_input(&input).err();
+        if problem == Some(InputProblem::LoggedOut)
+            || (status.is_client_error() && problem != Some(InputProblem::TooEarly))
+        {
+            load_token_from_stdin(
+                "Your token has expired. Please enter your new token.".red(),
+            );
+            return fetch(day, year, never_print);
+        }
+        if !status.is_success() && problem != Some(InputProblem::TooEarly) {
+            panic!("Received bad response from server: {status}");
+        }
+        if let Some(problem) = problem {
+            panic!("Advent of Code did not send back a puzzle input: {problem}");
+        }
+        cache_input(&in_file, &input);
+        if !never_print {
+            println!("{input}");
+        }
+        input
+    }
+}
+
+/// Download the input for every unlocked day of `year` that isn't already
+/// cached, waiting at least `min_delay` between requests.
+///
+/// Returns the days fetched. See [`fetch_years`] for details.
+pub fn fetch_year(year: i32, min_delay: Duration) -> Vec<u32> {
+    fetch_years([year], min_delay)
+        .into_iter()
+        .map(|(_, day)| day)
+        .collect()
+}
+
+/// Download the input for every unlocked day of each of `years` that isn't
+/// already cached, waiting at least `min_delay` between requests.
+///
+/// Unlike [`fetch`], this never asks for a token: it stops if none has been
+/// saved, or at the first authentication error, so it's safe to leave
+/// unattended. A day whose input looks wrong is skipped. Returns the
+/// `(year, day)` of each input fetched.
+///
+/// # Panics
+///
+/// If any year is before 2015, or if Advent of Code sends back a server error.
+pub fn fetch_years(
+    years: impl IntoIterator<Item = i32>,
+    min_delay: Duration,
+) -> Vec<(i32, u32)> {
+    let mut fetched = Vec::new();
+    let mut last_request: Option<Instant> = None;
+    'years: for year in years {
+        assert!(year >= 2015, "Invalid year");
+        make(&year_dir(year));
+        for day in 1..=25 {
+            let unlock = Utc
+                .with_ymd_and_hms(year, 12, day, 5, 0, 0)
+                .single()
+                .unwrap_or_else(|| {
+                    unreachable!("December days at 5AM are always valid")
+                });
+            if Utc::now() < unlock {
+                break;
+            }
+            let in_file = input_file(year, day);
+            if in_file.exists() {
+                continue;
+            }
+            if is_offline() {
+                eprintln!(
+                    "{}",
+                    "raoc is offline, so no inputs can be fetched; stopping.".yellow()
                 );
-                return fetch(day, year, never_print);
+                break 'years;
+            }
+            if !TOKEN_FILE.exists() {
+                // asking for a token would block an unattended fetch
+                eprintln!(
+                    "{}",
+                    "No token has been saved; stopping. Run any solution to enter \
+                     your token, then try again."
+                        .red()
+                );
+                break 'years;
+            }
+            if let Some(remaining) = last_request
+                .and_then(|last_request| min_delay.checked_sub(last_request.elapsed()))
+            {
+                wait("Waiting before the next request...".yellow(), remaining);
+            }
+
+            let resp = match get(&(base_url(year, day) + "/input"), true, "fetch_years")
+            {
+                Ok(resp) => resp,
+                Err(err) => {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "Couldn't fetch day {day} of {year}, as {err}; stopping."
+                        )
+                        .red()
+                    );
+                    break 'years;
+                },
+            };
+            last_request = Some(Instant::now());
+            let status = resp.status;
+            let input = strip_trailing_nl(resp.body);
+            match check_input(&input) {
+                _ if status == StatusCode::NOT_FOUND => {
+                    // later days of this year don't exist either
+                    eprintln!(
+                        "{} {} {}",
+                        "Day".yellow(),
+                        style(day).blue(),
+                        format!("of {year} does not exist; moving on.").yellow()
+                    );
+                    continue 'years;
+                },
+                Err(InputProblem::LoggedOut) => {},
+                _ if status.is_client_error() => {},
+                _ if !status.is_success() => {
+                    panic!("Received bad response from server: {status}")
+                },
+                Err(problem) => {
+                    eprintln!(
+                        "{} {}{}",
+                        format!("Day {day} of {year} was not fetched:").red(),
+                        problem.to_string().red(),
+                        "; moving on.".red()
+                    );
+                    continue;
+                },
+                Ok(()) => {
+                    cache_input(&in_file, &input);
+                    println!(
+                        "{} {} {}",
+                        "Fetched day".green(),
+                        style(day).blue(),
+                        format!("of {year}.").green()
+                    );
+                    fetched.push((year, day));
+                    continue;
+                },
             }
-            panic!("Received bad response from server: {}", resp.status());
-        }
-        let input = strip_trailing_nl(
-            resp.text()
-                .unwrap_or_else(|_| unreachable!("Response should be text")),
-        );
-        fs::write(in_file, &input).unwrap_or_else(|_| {
             eprintln!(
                 "{}",
-                "Warning: Failed to cache input file. Please check your permissions."
+                "Your token has expired; stopping. Run any solution to enter a new \
+                 token, then try again."
                     .red()
             );
-        });
-        if !never_print {
-            println!("{input}");
+            break 'years;
         }
-        input
     }
+    fetched
 }
 
 /// Submit a solution.
@@ -151,6 +395,29 @@ pub fn submit(day: u32, part: u32, year: i32, answer: impl Display) {
     submit_impl(day, part, year, answer.to_string());
 }
 
+/// Submit every answer in the submission queue (answers which couldn't be
+/// delivered when they were first submitted), oldest first, stopping if Advent
+/// of Code still can't be reached.
+///
+/// Queued answers are submitted as if by [`submit`], so answers to parts which
+/// have been solved since are not sent, and cooldowns are respected.
+///
+/// Returns the answers which are still queued.
+pub fn flush_submissions() -> Vec<QueuedAnswer> {
+    for queued in queued_answers() {
+        let delivered = if queued.day == 25 && queued.part == 2 {
+            submit_25(queued.year)
+        } else {
+            submit_impl(queued.day, queued.part, queued.year, queued.answer.clone())
+        };
+        if !delivered {
+            break;
+        }
+        dequeue(&queued);
+    }
+    queued_answers()
+}
+
 fn submit_already_solved(
     solution: &str,
     answer: &str,
@@ -179,7 +446,23 @@ fn submit_already_solved(
         style(part).blue(),
         solution.blue(),
     );
-    print_rank(&part_solutions[solution]);
+    if is_offline() {
+        if solution == answer {
+            println!("{}", "Your answer matches the cached solution.".green());
+        } else {
+            println!(
+                "{} {} {}",
+                "Unverified:".yellow(),
+                answer.blue(),
+                "does not match the cached solution, and can't be submitted while \
+                 raoc is offline."
+                    .yellow()
+            );
+        }
+    }
+    if let Some(response) = part_solutions.get(solution) {
+        print_rank(response);
+    }
 }
 
 fn delay(msg: &str) -> bool {
@@ -210,16 +493,27 @@ fn delay(msg: &str) -> bool {
     }
 }
 
-fn submit_impl(day: u32, part: u32, year: i32, answer: String) {
-    let submission_dir = &*DATA_DIR / year.to_string() / day.to_string();
-    make(&submission_dir);
-    let submissions = &submission_dir / "submissions.txt";
+/// Returns `false` if the answer couldn't be delivered, and was queued instead.
+fn submit_impl(day: u32, part: u32, year: i32, answer: String) -> bool {
+    make(&day_dir(year, day));
+    let submissions = submissions_file(year, day);
     let mut solutions = if submissions.exists() {
-        serde_json::from_reader(
-            fs::File::open(&submissions)
-                .unwrap_or_else(|_| unreachable!("File should exist")),
-        )
-        .unwrap_or_else(|_| unreachable!("File should be valid"))
+        read_submissions(&submissions).unwrap_or_else(|| {
+            let backup = day_dir(year, day) / format!("{SUBMISSIONS}.corrupt");
+            eprintln!(
+                "{} {}",
+                "Warning: Failed to parse the submission cache. A new one will be \
+                 started, and the old one has been moved to"
+                    .red(),
+                backup.display(),
+            );
+            fs::rename(&submissions, backup)
+                .expect("Moving the corrupt submission cache failed");
+            Submissions {
+                part_1: HashMap::new(),
+                part_2: HashMap::new(),
+            }
+        })
     } else {
         Submissions {
             part_1: HashMap::new(),
@@ -232,7 +526,7 @@ fn submit_impl(day: u32, part: u32, year: i32, answer: String) {
         _ => unreachable!("Part should be 1 or 2"),
     };
 
-    let solution_file = &submission_dir / format!("{part}.solution");
+    let solution_file = solution_file(year, day, part);
     #[allow(clippy::map_entry)]
     if solution_file.exists() {
         let solution = fs::read_to_string(solution_file)
@@ -248,35 +542,66 @@ fn submit_impl(day: u32, part: u32, year: i32, answer: String) {
             "has already been submitted.\nResponse was:".yellow(),
         );
         pretty_print(part_solutions[&answer].as_str());
+    } else if is_offline() {
+        println!(
+            "{} {} {}",
+            "Unverified:".yellow(),
+            answer.as_str().blue(),
+            "was not submitted, as raoc is offline.".yellow()
+        );
+        enqueue(year, day, part, &answer);
+        return false;
     } else {
         let mut msg;
+        let mut uncertain_attempts = 0;
         loop {
             println!(
                 "Submitting {} as the solution to part {}...",
                 answer.as_str().blue(),
                 style(part).blue()
             );
-            let resp = post(
+            let resp = match post(
                 &(base_url(year, day) + "/answer"),
                 true,
                 HashMap::from([
                     ("level", part.to_string()),
                     ("answer", answer.to_string()),
                 ]),
-            );
-            if !resp.status().is_success() {
-                if resp.status().is_client_error() {
+                "submit",
+            ) {
+                Ok(resp) => resp,
+                Err(SendError::MaybeSent) if uncertain_attempts < MAX_RETRIES => {
+                    match check_answer_accepted(day, part, year, &answer) {
+                        Some(true) => {
+                            msg = "That's the right answer!".to_string();
+                            break;
+                        },
+                        Some(false) => {
+                            uncertain_attempts += 1;
+                            continue;
+                        },
+                        None => {
+                            enqueue(year, day, part, &answer);
+                            return false;
+                        },
+                    }
+                },
+                Err(_) => {
+                    enqueue(year, day, part, &answer);
+                    return false;
+                },
+            };
+            if !resp.status.is_success() {
+                if resp.status.is_client_error() {
                     load_token_from_stdin(
                         "Your token has expired. Please enter your new token.".red(),
                     );
                     continue;
                 }
-                panic!("Received bad response from server: {}", resp.status());
+                panic!("Received bad response from server: {}", resp.status);
             }
 
-            let resp_text = resp
-                .text()
-                .unwrap_or_else(|_| unreachable!("Response should be text"));
+            let resp_text = resp.body;
             msg = message_from_body(&resp_text);
             if !delay(&msg) {
                 break;
@@ -284,7 +609,8 @@ fn submit_impl(day: u32, part: u32, year: i32, answer: String) {
         }
         if msg.starts_with("That's the") {
             print_rank(&msg);
-            fs::write(solution_file, &answer).expect("Writing solution cache failed");
+            write_atomic(&solution_file, &answer)
+                .expect("Writing solution cache failed");
             calculate_practice_result(day, part, year);
             if part == 1 {
                 open_page(&(base_url(year, day) + "#part2"));
@@ -293,40 +619,92 @@ fn submit_impl(day: u32, part: u32, year: i32, answer: String) {
             pretty_print(&msg);
         }
 
-        part_solutions.insert(answer, msg);
-        fs::write(submissions, serde_json::to_string(&solutions).unwrap())
+        let _lock = CacheLock::acquire(&submissions);
+        // another process may have submitted an answer since the cache was read
+        let mut solutions = read_submissions(&submissions).unwrap_or(solutions);
+        match part {
+            1 => &mut solutions.part_1,
+            2 => &mut solutions.part_2,
+            _ => unreachable!("Part should be 1 or 2"),
+        }
+        .insert(answer, msg);
+        write_atomic(&submissions, serde_json::to_string(&solutions).unwrap())
             .expect("Writing submissions cache failed");
     }
+    true
 }
-fn submit_25(year: &str) {
-    let resp = loop {
+
+/// Returns `false` if the request couldn't be delivered, and was queued
+/// instead.
+fn submit_25(year: i32) -> bool {
+    let mut uncertain_attempts = 0;
+    let msg = loop {
         println!(
             "{} {}{}",
             "Finishing Advent of Code".green(),
-            year.blue(),
+            style(year).blue(),
             '!'.green(),
         );
-        let resp = post(
+        let resp = match post(
             &(base_url(year, 25) + "/answer"),
             true,
             HashMap::from([("level", "2"), ("answer", "0")]),
-        );
-        if resp.status().is_success() {
-            break resp;
-        } else if resp.status().is_client_error() {
+            "submit",
+        ) {
+            Ok(resp) => resp,
+            Err(SendError::MaybeSent) if uncertain_attempts < MAX_RETRIES => {
+                match check_answer_accepted(25, 2, year, "") {
+                    Some(true) => {
+                        break "Congratulations! You've finished every puzzle."
+                            .to_string();
+                    },
+                    Some(false) => {
+                        uncertain_attempts += 1;
+                        continue;
+                    },
+                    None => {
+                        enqueue(year, 25, 2, "");
+                        return false;
+                    },
+                }
+            },
+            Err(_) => {
+                enqueue(year, 25, 2, "");
+                return false;
+            },
+        };
+        if resp.status.is_success() {
+            break message_from_body(&resp.body);
+        } else if resp.status.is_client_error() {
             load_token_from_stdin(
                 "Your token has expired. Please enter your new token.".red(),
             );
         } else {
-            panic!("Received bad response from server: {}", resp.status());
+            panic!("Received bad response from server: {}", resp.status);
         }
     };
 
     println!("Response from the server:");
-    println!(
-        "{}",
-        message_from_body(&resp.text().expect("Response should be text")),
-    );
+    println!("{msg}");
+    true
+}
+
+/// The answer, parse and solve timings of a solution run by [`run_part`], or
+/// the panic message if it panicked; `None` if it ran out of time.
+type SolutionRun<M> = Option<Result<(M, Stage, Stage), String>>;
+
+/// Parse `raw` and run the solution on it, timing each.
+fn timed_run<U, M>(
+    raw: &str,
+    parse_raw: impl FnOnce(&str) -> U,
+    solution: impl FnOnce(U) -> M,
+) -> (M, Stage, Stage) {
+    let stopwatch = Stopwatch::start();
+    let parsed = parse_raw(raw);
+    let parse = stopwatch.stop();
+    let stopwatch = Stopwatch::start();
+    let answer = solution(parsed);
+    (answer, parse, stopwatch.stop())
 }
 
 /// Run the functions only if we haven't seen a solution.
@@ -334,6 +712,10 @@ fn submit_25(year: &str) {
 /// Will also run solutions if `--force-run` or `--practice` is passed on the
 /// command line.
 ///
+/// See [`lazy_submit_part`] for the `--verify` mode, the time limit, and how
+/// panics are handled; the outcome of each part is returned. To support
+/// `--bench`, use [`lazy_submit_benchable`] instead.
+///
 /// The solution for part 2 will be ignored if day is 25.
 pub fn lazy_submit<U, S1: MaybeDisplay, S2: MaybeDisplay>(
     day: u32,
@@ -341,9 +723,11 @@ pub fn lazy_submit<U, S1: MaybeDisplay, S2: MaybeDisplay>(
     solution_part_1: impl FnOnce(U) -> S1,
     solution_part_2: impl FnOnce(U) -> S2,
     mut parse_raw: impl FnMut(&str) -> U,
-) {
-    lazy_submit_part(day, year, 1, solution_part_1, &mut parse_raw);
-    lazy_submit_part(day, year, 2, solution_part_2, &mut parse_raw);
+) -> [PartOutcome; 2] {
+    [
+        lazy_submit_part(day, year, 1, solution_part_1, &mut parse_raw),
+        lazy_submit_part(day, year, 2, solution_part_2, &mut parse_raw),
+    ]
 }
 
 /// Run the function only if we haven't seen a solution.
@@ -351,6 +735,31 @@ pub fn lazy_submit<U, S1: MaybeDisplay, S2: MaybeDisplay>(
 /// Will also run solution if `--force-run` or `--practice` is passed on the
 /// command line.
 ///
+/// If `--verify` is passed on the command line, the solution is run against
+/// the cached input, and its answer is compared with the cached accepted
+/// answer instead of being submitted; no requests are made, but the run is
+/// timed and recorded as usual.
+///
+/// Benchmarking needs to run the solution repeatedly, so if `--bench` is
+/// passed on the command line, the part is skipped; use
+/// [`lazy_submit_part_benchable`] to benchmark it instead.
+///
+/// In offline mode, a part which has already been solved is run as if
+/// `--verify` had been passed, and its answer compared with the cached
+/// solution; new answers are queued to be submitted later.
+///
+/// If `--time-limit=SECONDS` is passed on the command line (or
+/// `time_limit.txt` in the data directory holds a number of seconds), a
+/// solution which runs for longer is reported as having timed out, and its
+/// answer is not submitted (or verified). The solution runs on the current
+/// thread, so it can't be stopped early; use [`lazy_submit_part_abandonable`]
+/// to give up on it once the limit is reached.
+///
+/// If the input parser or the solution panics, the panic is reported (along
+/// with how long the solution had been running) in place of an answer. The
+/// returned [`PartOutcome`] says whether the solution panicked or timed out;
+/// panics which abort the process can't be caught.
+///
 /// If the day is 25 and the part is 2, will ignore the solution.
 pub fn lazy_submit_part<U, M: MaybeDisplay>(
     day: u32,
@@ -358,67 +767,604 @@ pub fn lazy_submit_part<U, M: MaybeDisplay>(
     part: u32,
     solution_part_1: impl FnOnce(U) -> M,
     parse_raw: impl FnOnce(&str) -> U,
-) {
-    let submission_dir = &*DATA_DIR / year.to_string() / day.to_string();
-    make(&submission_dir);
+) -> PartOutcome {
+    if is_bench_mode() && !is_verify_mode() {
+        return skip_bench(day, part, "lazy_submit_benchable");
+    }
+    submit_part(day, year, part, solution_part_1, parse_raw)
+}
+
+/// Like [`lazy_submit`], but if `--bench` is passed on the command line, each
+/// part is benchmarked instead; see [`lazy_submit_part_benchable`].
+///
+/// The solution for part 2 will be ignored if day is 25.
+pub fn lazy_submit_benchable<U, S1: MaybeDisplay, S2: MaybeDisplay>(
+    day: u32,
+    year: i32,
+    solution_part_1: impl Fn(U) -> S1,
+    solution_part_2: impl Fn(U) -> S2,
+    mut parse_raw: impl FnMut(&str) -> U,
+) -> [PartOutcome; 2] {
+    [
+        lazy_submit_part_benchable(day, year, 1, solution_part_1, &mut parse_raw),
+        lazy_submit_part_benchable(day, year, 2, solution_part_2, &mut parse_raw),
+    ]
+}
+
+/// Like [`lazy_submit_part`], but if `--bench` is passed on the command line,
+/// the input is parsed and the solution run on the cached input repeatedly,
+/// and timings for each are reported; nothing is submitted, and no requests
+/// are made.
+///
+/// If the day is 25 and the part is 2, will ignore the solution.
+pub fn lazy_submit_part_benchable<U, M: MaybeDisplay>(
+    day: u32,
+    year: i32,
+    part: u32,
+    solution_part_1: impl Fn(U) -> M,
+    parse_raw: impl FnMut(&str) -> U,
+) -> PartOutcome {
+    if is_bench_mode() && !is_verify_mode() {
+        return bench_part(day, year, part, solution_part_1, parse_raw);
+    }
+    submit_part(day, year, part, solution_part_1, parse_raw)
+}
+
+/// Run the solution on the current thread, and submit (or verify) its answer.
+fn submit_part<U, M: MaybeDisplay>(
+    day: u32,
+    year: i32,
+    part: u32,
+    solution_part_1: impl FnOnce(U) -> M,
+    parse_raw: impl FnOnce(&str) -> U,
+) -> PartOutcome {
+    run_part(day, year, part, |msg, raw, time_limit| {
+        let start = Instant::now();
+        let result = work(msg, || {
+            catch_panic(|| timed_run(&raw, parse_raw, solution_part_1))
+        });
+        // the solution couldn't be stopped, but its answer can still be ignored
+        let overran = time_limit.is_some_and(|limit| start.elapsed() > limit);
+        (!overran).then_some(result)
+    })
+}
+
+/// Like [`lazy_submit`], but solutions which run for longer than the time
+/// limit are abandoned; see [`lazy_submit_part_abandonable`].
+///
+/// The solution for part 2 will be ignored if day is 25.
+pub fn lazy_submit_abandonable<
+    U,
+    S1: MaybeDisplay + Send + 'static,
+    S2: MaybeDisplay + Send + 'static,
+>(
+    day: u32,
+    year: i32,
+    solution_part_1: impl FnOnce(U) -> S1 + Send + 'static,
+    solution_part_2: impl FnOnce(U) -> S2 + Send + 'static,
+    parse_raw: impl Fn(&str) -> U + Send + Sync + 'static,
+) -> [PartOutcome; 2] {
+    // each part's solution may be abandoned while still parsing
+    let parse_raw = Arc::new(parse_raw);
+    let parse_raw_2 = Arc::clone(&parse_raw);
+    [
+        lazy_submit_part_abandonable(
+            day,
+            year,
+            1,
+            solution_part_1,
+            move |raw: &str| parse_raw(raw),
+        ),
+        lazy_submit_part_abandonable(
+            day,
+            year,
+            2,
+            solution_part_2,
+            move |raw: &str| parse_raw_2(raw),
+        ),
+    ]
+}
+
+/// Like [`lazy_submit_part`], but a solution which runs for longer than the
+/// time limit is abandoned rather than waited for.
+///
+/// If a time limit is configured, the solution runs on its own thread, which
+/// keeps running in the background until it finishes or the program exits;
+/// see [`work_with_time_limit`]. Otherwise, it runs on the current thread.
+///
+/// An abandoned solution still counts towards the memory usage reported for
+/// later runs, and any [`progress`](crate::progress) it reports is shown by
+/// their spinners.
+///
+/// Like [`lazy_submit_part`], the part is skipped if `--bench` is passed on
+/// the command line.
+///
+/// If the day is 25 and the part is 2, will ignore the solution.
+pub fn lazy_submit_part_abandonable<U, M: MaybeDisplay + Send + 'static>(
+    day: u32,
+    year: i32,
+    part: u32,
+    solution_part_1: impl FnOnce(U) -> M + Send + 'static,
+    parse_raw: impl FnOnce(&str) -> U + Send + 'static,
+) -> PartOutcome {
+    if is_bench_mode() && !is_verify_mode() {
+        return skip_bench(day, part, "lazy_submit_benchable");
+    }
+    run_part(day, year, part, |msg, raw, time_limit| {
+        work_with_time_limit(msg, time_limit, move || {
+            catch_panic(|| timed_run(&raw, parse_raw, solution_part_1))
+        })
+    })
+}
+
+/// Submit (or verify) the answer to `part`, using `run` to run the solution on
+/// the input with a spinner showing the given message, and the time limit.
+fn run_part<M: MaybeDisplay>(
+    day: u32,
+    year: i32,
+    part: u32,
+    run: impl FnOnce(String, String, Option<Duration>) -> SolutionRun<M>,
+) -> PartOutcome {
+    if is_verify_mode() {
+        return verify_part(day, year, part, run);
+    }
+    if is_offline()
+        && !is_practice_mode()
+        && !(day == 25 && part == 2)
+        && solution_file(year, day, part).exists()
+    {
+        // nothing can be submitted, but the answer can still be checked
+        println!(
+            "{} {}",
+            "Unverified:".yellow(),
+            "raoc is offline, so the answer will only be compared with the cached \
+             solution."
+                .yellow()
+        );
+        return verify_part(day, year, part, run);
+    }
+    make(&day_dir(year, day));
     if day == 25 && part == 2 {
         // don't try to submit part 2 if part 1 isn't solved
-        if (&submission_dir / "1.solution").exists() {
-            submit_25(&year.to_string());
+        if solution_file(year, day, 1).exists() {
+            submit_25(year);
         } else {
-            return;
+            return PartOutcome::Finished;
         }
     }
-    let solution_file = &submission_dir / format!("{part}.solution");
+    let solution_file = solution_file(year, day, part);
     if !solution_file.exists()
         || must_run_solutions()
         || (is_practice_mode()
             && practice_result_for(day, year).1.len() < part as usize)
     {
-        let answer = work(
+        let start = Instant::now();
+        let raw = fetch(day, year, false);
+        let fetch_time = start.elapsed();
+        let time_limit = time_limit();
+        let start = Instant::now();
+        let result = run(
             format!(
                 "{} {} {}",
                 "Running part".yellow(),
                 style(part).blue(),
                 "solution".yellow(),
             ),
-            || {
-                let raw = fetch(day, year, false);
-                solution_part_1(parse_raw(&raw))
-            },
-        )
-        .into_solution();
+            raw,
+            time_limit,
+        );
+        let (answer, parse, solve) =
+            match finished_run(day, part, start, time_limit, result) {
+                Ok(run) => run,
+                Err(outcome) => return outcome,
+            };
+        print_run_timings(fetch_time, &parse, &solve);
+        record_run(year, day, part, fetch_time, &parse, &solve);
+        let answer = answer.into_solution();
         if let Some(answer) = answer {
             submit(day, part, year, answer);
         }
     } else {
-        // load cached solutions
-        let submissions = submission_dir / "submissions.json";
-        let solutions: Submissions = serde_json::from_str(
-            &fs::read_to_string(submissions).unwrap_or_else(|_| {
-                unreachable!(
-                    "Failed to read submission cache, which must exist as this part \
-                     has already been solved"
-                )
-            }),
-        )
-        .unwrap_or_else(|_| unreachable!("Failed to parse submission cache"));
-
-        let solution = fs::read_to_string(solution_file)
-            .unwrap_or_else(|_| unreachable!("Solution file was corrupt"));
-        let response = match part {
-            1 => &solutions.part_1[&solution],
-            2 => &solutions.part_2[&solution],
-            _ => unreachable!("Part should be 1 or 2"),
-        };
+        let solution =
+            fs::read_to_string(solution_file).expect("Failed to read solution cache");
         println!(
             "Day {} part {} has already been solved.\nThe solution was {}",
             style(day).blue(),
             style(part).blue(),
-            solution.blue(),
+            solution.as_str().blue(),
         );
-        print_rank(response);
+        // load cached response
+        let response =
+            read_submissions(&submissions_file(year, day)).and_then(|mut solutions| {
+                match part {
+                    1 => solutions.part_1.remove(&solution),
+                    2 => solutions.part_2.remove(&solution),
+                    _ => unreachable!("Part should be 1 or 2"),
+                }
+            });
+        if let Some(response) = response {
+            print_rank(&response);
+        } else {
+            eprintln!(
+                "{}",
+                "Warning: No submission was recorded for this solution. Run `raoc \
+                 validate` to check your cache for problems."
+                    .yellow()
+            );
+        }
+    }
+    PartOutcome::Finished
+}
+
+/// Run the solution to `part` against the cached input with `run`, and
+/// compare its answer with the cached accepted answer.
+fn verify_part<M: MaybeDisplay>(
+    day: u32,
+    year: i32,
+    part: u32,
+    run: impl FnOnce(String, String, Option<Duration>) -> SolutionRun<M>,
+) -> PartOutcome {
+    if day == 25 && part == 2 {
+        // there's no answer to check
+        return PartOutcome::Finished;
     }
+    let Ok(expected) = fs::read_to_string(solution_file(year, day, part)) else {
+        report_skipped(day, part, "it hasn't been solved");
+        return PartOutcome::Finished;
+    };
+    let start = Instant::now();
+    let Ok(raw) = fs::read_to_string(input_file(year, day)) else {
+        report_skipped(day, part, "the input isn't cached");
+        return PartOutcome::Finished;
+    };
+    let fetch_time = start.elapsed();
+    let time_limit = time_limit();
+    let start = Instant::now();
+    let result = run(
+        format!(
+            "{} {} {}",
+            "Verifying part".yellow(),
+            style(part).blue(),
+            "solution".yellow(),
+        ),
+        raw,
+        time_limit,
+    );
+    let (answer, parse, solve) =
+        match finished_run(day, part, start, time_limit, result) {
+            Ok(run) => run,
+            Err(outcome) => return outcome,
+        };
+    record_run(year, day, part, fetch_time, &parse, &solve);
+    report_verification(
+        day,
+        part,
+        &expected,
+        answer.into_solution(),
+        parse.time + solve.time,
+    );
+    PartOutcome::Finished
+}
+
+/// Repeatedly parse the cached input and run the solution to `part` on it,
+/// reporting how long each takes.
+fn bench_part<U, M: MaybeDisplay>(
+    day: u32,
+    year: i32,
+    part: u32,
+    solution: impl Fn(U) -> M,
+    mut parse_raw: impl FnMut(&str) -> U,
+) -> PartOutcome {
+    if day == 25 && part == 2 {
+        // there's no solution to run
+        return PartOutcome::Finished;
+    }
+    let Ok(raw) = fs::read_to_string(input_file(year, day)) else {
+        report_skipped(day, part, "the input isn't cached");
+        return PartOutcome::Finished;
+    };
+    eprintln!(
+        "{} {}{}",
+        "Benchmarking part".yellow(),
+        style(part).blue(),
+        "...".yellow()
+    );
+    let mut bench = Benchmark::new();
+    let mut answer = None;
+    let start = Instant::now();
+    let result = catch_panic(|| {
+        while bench.keep_going() {
+            let start = Instant::now();
+            let parsed = parse_raw(&raw);
+            let parse_time = start.elapsed();
+            let start = Instant::now();
+            let result = solution(parsed);
+            bench.record(parse_time, start.elapsed());
+            if answer.is_none() {
+                answer = Some(result);
+            } else {
+                black_box(result);
+            }
+        }
+    });
+    if let Err(outcome) = finished_run(day, part, start, None, Some(result)) {
+        return outcome;
+    }
+    bench.report(day, part, answer.and_then(MaybeDisplay::into_solution));
+    PartOutcome::Finished
+}
+
+/// Fetch the private leaderboard with the given `id` for `year`.
+///
+/// Leaderboards are cached in the data directory, and will not be re-requested
+/// until the cached copy is at least 15 minutes old, as requested by Advent of
+/// Code.
+///
+/// # Panics
+///
+/// If the year is invalid, or the leaderboard does not exist or is not visible
+/// to you.
+#[must_use]
+pub fn private_leaderboard(year: i32, id: u64) -> PrivateLeaderboard {
+    assert!(year >= 2015, "Invalid year");
+    make(&(year_dir(year) / PRIVATE_LEADERBOARDS));
+    let cache_file = private_leaderboard_file(year, id);
+    if is_fresh(&cache_file, LEADERBOARD_REFRESH) {
+        let data = fs::read_to_string(&cache_file)
+            .expect("Failed to read private leaderboard cache.");
+        // Advent of Code asks for leaderboards to be requested at most every
+        // 15 minutes, so don't refetch one just because its cache is broken
+        return serde_json::from_str(&data).unwrap_or_else(|_| {
+            panic!(
+                "Failed to parse the cached private leaderboard {id}. Delete {} to \
+                 fetch it again.",
+                cache_file.display()
+            )
+        });
+    }
+
+    let resp = match get(
+        &private_leaderboard_url(year, id),
+        true,
+        "private_leaderboard",
+    ) {
+        Ok(resp) => resp,
+        Err(err) => {
+            let Some(leaderboard) = read_stale(&cache_file) else {
+                panic!("Couldn't fetch private leaderboard {id}, as {err}.");
+            };
+            warn_stale(&format!("private leaderboard {id}"), &err);
+            return leaderboard;
+        },
+    };
+    if !resp.status.is_success() {
+        if resp.status == StatusCode::NOT_FOUND {
+            panic!("Private leaderboard {id} does not exist or is not visible to you.");
+        }
+        if resp.status.is_client_error() {
+            load_token_from_stdin(
+                "Your token has expired. Please enter your new token.".red(),
+            );
+            return private_leaderboard(year, id);
+        }
+        panic!("Received bad response from server: {}", resp.status);
+    }
+    let data = resp.body;
+    // Advent of Code redirects to an HTML page if the leaderboard isn't
+    // visible to us, so make sure we got JSON before caching it
+    let leaderboard = serde_json::from_str(&data).unwrap_or_else(|_| {
+        panic!(
+            "Failed to parse private leaderboard {id}. Please check that it exists \
+             and that you are a member of it."
+        )
+    });
+    write_atomic(&cache_file, &data).unwrap_or_else(|_| {
+        eprintln!(
+            "{}",
+            "Warning: Failed to cache private leaderboard. Please check your \
+             permissions."
+                .red()
+        );
+    });
+    leaderboard
+}
+
+/// Fetch your personal statistics for `year`.
+///
+/// These include your time, rank and score for every part you have solved,
+/// even if you did not make the global leaderboard. Statistics are cached in
+/// the data directory, and will not be re-requested until the cached copy is
+/// at least 15 minutes old.
+///
+/// # Panics
+///
+/// If the year is invalid.
+#[must_use]
+pub fn personal_stats(year: i32) -> PersonalStats {
+    assert!(year >= 2015, "Invalid year");
+    make(&year_dir(year));
+    let cache_file = year_dir(year) / PERSONAL_STATS;
+    if is_fresh(&cache_file, LEADERBOARD_REFRESH) {
+        let data = fs::read_to_string(&cache_file)
+            .expect("Failed to read personal statistics cache.");
+        if let Ok(stats) = serde_json::from_str(&data) {
+            return stats;
+        }
+    }
+
+    let resp = match get(&personal_stats_url(year), true, "personal_stats") {
+        Ok(resp) => resp,
+        Err(err) => {
+            let Some(stats) = read_stale(&cache_file) else {
+                panic!("Couldn't fetch your statistics for {year}, as {err}.");
+            };
+            warn_stale(&format!("your statistics for {year}"), &err);
+            return stats;
+        },
+    };
+    if !resp.status.is_success() {
+        if resp.status.is_client_error() {
+            load_token_from_stdin(
+                "Your token has expired. Please enter your new token.".red(),
+            );
+            return personal_stats(year);
+        }
+        panic!("Received bad response from server: {}", resp.status);
+    }
+    let page = resp.body;
+    let stats = match pre_from_body(&page) {
+        Some(table) => PersonalStats::parse(year, &table),
+        None if is_logged_out(&page) => {
+            load_token_from_stdin(
+                "Your token has expired. Please enter your new token.".red(),
+            );
+            return personal_stats(year);
+        },
+        // No stars collected yet
+        None => PersonalStats::parse(year, ""),
+    };
+    write_atomic(
+        &cache_file,
+        serde_json::to_string(&stats).expect("Serialising should never fail"),
+    )
+    .unwrap_or_else(|_| {
+        eprintln!(
+            "{}",
+            "Warning: Failed to cache personal statistics. Please check your \
+             permissions."
+                .red()
+        );
+    });
+    stats
+}
+
+/// Fetch the calendar for `year`, showing how many stars you have collected
+/// for each unlocked day.
+///
+/// The calendar is cached in the data directory, and will not be re-requested
+/// until the cached copy is at least 15 minutes old. Stars for solutions that
+/// have been cached since then are included anyway.
+///
+/// # Panics
+///
+/// If the year is invalid.
+#[must_use]
+pub fn calendar(year: i32) -> Calendar {
+    assert!(year >= 2015, "Invalid year");
+    make(&year_dir(year));
+    let cache_file = year_dir(year) / CALENDAR;
+    if is_fresh(&cache_file, LEADERBOARD_REFRESH) {
+        let data =
+            fs::read_to_string(&cache_file).expect("Failed to read calendar cache.");
+        if let Ok(calendar) = serde_json::from_str::<Calendar>(&data) {
+            return calendar.merge(&Calendar::from_cache(year));
+        }
+    }
+
+    let resp = match get(&calendar_url(year), true, "calendar") {
+        Ok(resp) => resp,
+        Err(err) => {
+            // the cached solutions are enough to fill the calendar in
+            warn_stale(&format!("the calendar for {year}"), &err);
+            let cached = Calendar::from_cache(year);
+            return match read_stale::<Calendar>(&cache_file) {
+                Some(calendar) => calendar.merge(&cached),
+                None => cached,
+            };
+        },
+    };
+    if !resp.status.is_success() {
+        if resp.status.is_client_error() {
+            load_token_from_stdin(
+                "Your token has expired. Please enter your new token.".red(),
+            );
+            return calendar(year);
+        }
+        panic!("Received bad response from server: {}", resp.status);
+    }
+    let page = resp.body;
+    if is_logged_out(&page) {
+        load_token_from_stdin(
+            "Your token has expired. Please enter your new token.".red(),
+        );
+        return calendar(year);
+    }
+    let calendar = Calendar::parse(year, &page);
+    write_atomic(
+        &cache_file,
+        serde_json::to_string(&calendar).expect("Serialising should never fail"),
+    )
+    .unwrap_or_else(|_| {
+        eprintln!(
+            "{}",
+            "Warning: Failed to cache calendar. Please check your permissions.".red()
+        );
+    });
+    calendar.merge(&Calendar::from_cache(year))
+}
+
+/// Fetch the global completion statistics for `year`.
+///
+/// These are the number of users who have solved each part of each day.
+/// Statistics are cached in the data directory, and will not be re-requested
+/// until the cached copy is at least 15 minutes old.
+///
+/// # Panics
+///
+/// If the year is invalid.
+#[must_use]
+pub fn global_stats(year: i32) -> GlobalStats {
+    try_global_stats(year).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Fetch the global completion statistics for `year`, like [`global_stats`],
+/// but return an error instead of panicking if they can't be fetched and
+/// nothing is cached.
+pub(crate) fn try_global_stats(year: i32) -> Result<GlobalStats, String> {
+    assert!(year >= 2015, "Invalid year");
+    make(&year_dir(year));
+    let cache_file = year_dir(year) / GLOBAL_STATS;
+    if is_fresh(&cache_file, LEADERBOARD_REFRESH) {
+        let data = fs::read_to_string(&cache_file)
+            .expect("Failed to read global statistics cache.");
+        if let Ok(stats) = serde_json::from_str(&data) {
+            return Ok(stats);
+        }
+    }
+
+    let resp = match get(&global_stats_url(year), false, "global_stats") {
+        Ok(resp) => resp,
+        Err(err) => {
+            let Some(stats) = read_stale(&cache_file) else {
+                return Err(format!(
+                    "Couldn't fetch the global statistics for {year}, as {err}."
+                ));
+            };
+            warn_stale(&format!("the global statistics for {year}"), &err);
+            return Ok(stats);
+        },
+    };
+    if !resp.status.is_success() {
+        return Err(format!(
+            "Received bad response from server: {}",
+            resp.status
+        ));
+    }
+    let page = resp.body;
+    let stats = GlobalStats::parse(year, &pre_from_body(&page).unwrap_or_default());
+    write_atomic(
+        &cache_file,
+        serde_json::to_string(&stats).expect("Serialising should never fail"),
+    )
+    .unwrap_or_else(|_| {
+        eprintln!(
+            "{}",
+            "Warning: Failed to cache global statistics. Please check your \
+             permissions."
+                .red()
+        );
+    });
+    Ok(stats)
 }
 
 /// Retrieve the example input and answer for the specified puzzle.
@@ -461,7 +1407,7 @@ pub fn get_sample_input(day: u32, part: u32, year: i32) -> Option<(String, Strin
     //     return part_tests.clone();
     // }
 
-    // let resp = get_text(&(base_url(year, day) + "/input"), true);
+    // let resp = get_text(&(base_url(year, day) + "/input"), true, "fetch");
     // let soup = tl::parse(&resp, tl::ParserOptions::new()).unwrap_or_else(|_|
     // {     unreachable!("Advent of Code server returned a bad response")
     // });
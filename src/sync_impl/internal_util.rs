@@ -2,30 +2,47 @@ use std::fmt::Display;
 use std::path::Path;
 use std::sync::atomic;
 use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::time::{Duration, Instant};
-use std::{fs, io, thread};
+use std::{fs, io, panic, thread};
 
 use chrono::{Datelike, NaiveDate, Utc};
 use crossterm::style::{style, Stylize};
 use lazy_static::lazy_static;
 use pathdiv::PathDiv;
-use reqwest::blocking::{Client, Response};
+use reqwest::blocking::{Client, RequestBuilder, Response};
 use reqwest::header;
 
+use super::interface::try_global_stats;
+use crate::audit::{self, RequestFailure};
+use crate::cache::{day_dir, leaderboards_file, practice_dir, write_atomic, CacheLock};
 use crate::data::{
+    base_url,
     leaderboard_url,
-    DATA_DIR,
     GOLD,
-    PRACTICE_DATA_DIR,
+    MAX_RETRIES,
+    REQUEST_TIMEOUT,
     TOKEN_FILE,
     USER_AGENT,
 };
 use crate::internal_util::{
+    answer_accepted,
+    backoff,
+    duration_setting,
+    extrapolate_rank,
+    format_clock,
     format_time,
     get_leaderboard_time,
+    go_offline,
+    is_offline,
     is_practice_mode,
     strip_trailing_nl,
+    Reply,
+    SendError,
+    Spinner,
 };
+use crate::progress::{progress_status, reset_progress};
+use crate::throttle;
 
 pub(crate) fn load_token_from_stdin(why: impl Display) -> String {
     eprintln!("{why}");
@@ -35,89 +52,128 @@ pub(crate) fn load_token_from_stdin(why: impl Display) -> String {
         .read_line(&mut token)
         .expect("Failed to read token.");
     token = strip_trailing_nl(token);
-    fs::write(&*TOKEN_FILE, &token)
+    write_atomic(&TOKEN_FILE, &token)
         .expect("Failed to write token file. Check your permissions.");
+    // pages fetched with the old token may just have been login pages
+    throttle::forget_successes();
     token
 }
 
 /// Wait the specified time, displaying a countdown, a spinner, and a message.
+///
+/// If stderr isn't a terminal, a status line is printed once a minute instead;
+/// pass `--spinner` or `--no-spinner` on the command line to choose either.
 pub fn wait(msg: impl Display, time: Duration) {
     let start = Instant::now();
     let end = start + time;
     let mut time_left = end - start;
+    let mut spinner = Spinner::new();
     while {
-        eprint!(
-            "\r{} {} {:02}{}{:02}{}{:02}",
-            msg,
-            match (start.elapsed().as_millis() / 80) % 10 {
-                0 => '⠋',
-                1 => '⠙',
-                2 => '⠹',
-                3 => '⠸',
-                4 => '⠼',
-                5 => '⠴',
-                6 => '⠦',
-                7 => '⠧',
-                8 => '⠇',
-                9 => '⠏',
-                _ => unreachable!(),
-            }
-            .yellow(),
-            style(time_left.as_secs() / 3600).yellow(),
-            ':'.yellow(),
-            style(time_left.as_secs() / 60 % 60).yellow(),
-            ':'.yellow(),
-            style(time_left.as_secs() % 60).yellow(),
+        spinner.draw(
+            &msg,
+            start.elapsed(),
+            &format_clock(time_left).yellow().to_string(),
         );
         time_left.as_secs() > 0
     } {
         thread::sleep(Duration::from_millis(100));
         time_left = end - Instant::now();
     }
-    eprintln!();
+    spinner.finish();
+}
+
+/// The status shown by the spinner of [`work`]: the elapsed time, and the
+/// worker's progress if it has reported any.
+fn work_status(elapsed: Duration) -> String {
+    let clock = format_clock(elapsed).yellow();
+    match progress_status(elapsed) {
+        Some(progress) => format!("{clock} {progress}"),
+        None => clock.to_string(),
+    }
 }
 
 /// Run the given worker function, displaying a message, spinner, and elapsed
 /// timer.
+///
+/// If the worker reports its [`progress`](crate::progress()), the percentage
+/// done, rate and ETA are shown too. Like [`wait`], the spinner is replaced by
+/// a status line once a minute if stderr isn't a terminal.
 pub fn work<T>(msg: impl Display + Sync, worker: impl FnOnce() -> T) -> T {
     let start = Instant::now();
     let is_done = AtomicBool::new(false);
-    thread::scope(|scope| {
+    let result = thread::scope(|scope| {
         scope.spawn(|| {
+            let mut spinner = Spinner::new();
             while !is_done.load(atomic::Ordering::Relaxed) {
                 let elapsed = start.elapsed();
-                eprint!(
-                    "\r{} {} {:02}{}{:02}{}{:02}",
-                    msg,
-                    match (start.elapsed().as_millis() / 80) % 10 {
-                        0 => '⠋',
-                        1 => '⠙',
-                        2 => '⠹',
-                        3 => '⠸',
-                        4 => '⠼',
-                        5 => '⠴',
-                        6 => '⠦',
-                        7 => '⠧',
-                        8 => '⠇',
-                        9 => '⠏',
-                        _ => unreachable!(),
-                    }
-                    .yellow(),
-                    style(elapsed.as_secs() / 3600).yellow(),
-                    ':'.yellow(),
-                    style(elapsed.as_secs() / 60 % 60).yellow(),
-                    ':'.yellow(),
-                    style(elapsed.as_secs() % 60).yellow(),
-                );
+                spinner.draw(&msg, elapsed, &work_status(elapsed));
                 thread::sleep(Duration::from_millis(100));
             }
-            eprintln!();
+            spinner.finish();
         });
         let result = worker();
         // let the spinner thread die in its own time
         is_done.store(true, atomic::Ordering::Relaxed);
         result
-    })
+    });
+    // only once the spinner has drawn its last frame
+    reset_progress();
+    result
+}
+
+/// Run the given worker function like [`work`], but give up on it once it has
+/// run for `time_limit` (if given), returning `None`.
+///
+/// Without a time limit, the worker simply runs on the current thread. With
+/// one, it runs on its own thread, which is abandoned rather than stopped if
+/// it runs out of time: it keeps running in the background until it finishes
+/// or the program exits. Any [`progress`](crate::progress) an abandoned worker
+/// reports is shown by later spinners, and its allocations are counted in
+/// their memory usage.
+pub fn work_with_time_limit<T: Send + 'static>(
+    msg: impl Display + Sync,
+    time_limit: Option<Duration>,
+    worker: impl FnOnce() -> T + Send + 'static,
+) -> Option<T> {
+    let Some(limit) = time_limit else {
+        return Some(work(msg, worker));
+    };
+    let start = Instant::now();
+    let (sender, receiver) = mpsc::channel();
+    let handle = thread::Builder::new()
+        .name("raoc-worker".into())
+        .spawn(move || {
+            // the receiver is gone if the worker was abandoned
+            let _ = sender.send(worker());
+        })
+        .expect("Failed to spawn the worker thread");
+    let mut spinner = Spinner::new();
+    loop {
+        let elapsed = start.elapsed();
+        spinner.draw(&msg, elapsed, &work_status(elapsed));
+        let Some(left) = limit.checked_sub(start.elapsed()) else {
+            spinner.finish();
+            reset_progress();
+            return None;
+        };
+        match receiver.recv_timeout(left.min(Duration::from_millis(100))) {
+            Ok(result) => {
+                spinner.finish();
+                reset_progress();
+                return Some(result);
+            },
+            Err(RecvTimeoutError::Timeout) => {},
+            Err(RecvTimeoutError::Disconnected) => {
+                spinner.finish();
+                reset_progress();
+                // the worker panicked; carry on panicking here, like `work`
+                let Err(payload) = handle.join() else {
+                    unreachable!("The worker always sends its result")
+                };
+                panic::resume_unwind(payload);
+            },
+        }
+    }
 }
 
 /// Make a directory, if it doesn't exist.
@@ -130,52 +186,195 @@ pub(crate) fn make(dir: &Path) {
 lazy_static! {
     static ref CLIENT: Client = Client::builder()
         .user_agent(USER_AGENT)
+        .timeout(duration_setting(TIMEOUT_FILE, REQUEST_TIMEOUT))
         .build()
         .expect("Failed to build reqwest client.");
 }
 
-pub(crate) fn get(url: &str, authenticate: bool) -> Response {
-    if authenticate {
-        CLIENT.get(url).header(header::COOKIE, get_cookie())
-    } else {
-        CLIENT.get(url)
+/// The number of seconds to wait for a response before giving up.
+const TIMEOUT_FILE: &str = "request_timeout.txt";
+
+/// Read the body of `resp`, and record the request in the audit log.
+fn read_reply(
+    method: &str,
+    url: &str,
+    authenticate: bool,
+    api: &str,
+    resp: Response,
+) -> Reply {
+    let reply = Reply {
+        status: resp.status(),
+        body: resp
+            .text()
+            .expect("Advent of Code sent back a bad response"),
+    };
+    audit::log_request(method, url, authenticate, Ok(&reply), api);
+    reply
+}
+
+/// Send the request built by `build`, retrying connection failures, timeouts
+/// and server errors with exponential backoff. Once the retries run out, a
+/// server error is returned as-is.
+///
+/// Unless `idempotent` is set, failures where the request may have reached
+/// Advent of Code (timeouts and server errors) are not retried; they're
+/// reported as [`SendError::MaybeSent`] instead, so that the caller can check
+/// what happened.
+///
+/// If Advent of Code can't be connected to, offline mode is switched on.
+fn send(
+    method: &str,
+    url: &str,
+    authenticate: bool,
+    api: &str,
+    idempotent: bool,
+    build: impl Fn() -> RequestBuilder,
+) -> Result<Reply, SendError> {
+    let mut attempt = 0;
+    loop {
+        let sent = build().send();
+        if let Err(err) = &sent {
+            let failure = RequestFailure::of(err);
+            audit::log_request(method, url, authenticate, Err(failure), api);
+        }
+        let problem = match sent {
+            Ok(resp) if resp.status().is_server_error() => {
+                let reply = read_reply(method, url, authenticate, api, resp);
+                if attempt == MAX_RETRIES {
+                    return Ok(reply);
+                }
+                if !idempotent {
+                    return Err(SendError::MaybeSent);
+                }
+                format!("Advent of Code returned {}.", reply.status)
+            },
+            Ok(resp) => return Ok(read_reply(method, url, authenticate, api, resp)),
+            Err(err) if err.is_connect() || (idempotent && err.is_timeout()) => {
+                if attempt == MAX_RETRIES {
+                    if err.is_connect() {
+                        go_offline();
+                    }
+                    return Err(SendError::NotSent(err));
+                }
+                if err.is_timeout() {
+                    "The request timed out.".into()
+                } else {
+                    "Failed to connect to Advent of Code.".into()
+                }
+            },
+            Err(err) if idempotent => return Err(SendError::NotSent(err)),
+            Err(_) => return Err(SendError::MaybeSent),
+        };
+        wait(
+            format!("{} {}", problem.yellow(), "Retrying in".yellow()),
+            backoff(attempt),
+        );
+        attempt += 1;
+        thread::sleep(throttle::reserve(url, true));
     }
-    .send()
-    .expect("Advent of Code sent back a bad response, or the network is down.")
 }
 
-pub(crate) fn get_text(url: &str, authenticate: bool) -> String {
-    get(url, authenticate)
-        .text()
-        .expect("Advent of Code sent back a bad response")
+/// Send a GET request to `url`, retrying failures.
+fn try_get(url: &str, authenticate: bool, api: &str) -> Result<Reply, SendError> {
+    let cookie = authenticate.then(get_cookie);
+    send("GET", url, authenticate, api, true, || {
+        let request = CLIENT.get(url);
+        match &cookie {
+            Some(cookie) => request.header(header::COOKIE, cookie),
+            None => request,
+        }
+    })
 }
 
+/// Send a GET request to `url` on behalf of the library function `api`.
+///
+/// Nothing is sent in offline mode, so that callers can fall back to their
+/// cache; offline mode is switched on if Advent of Code can't be reached.
+pub(crate) fn get(
+    url: &str,
+    authenticate: bool,
+    api: &str,
+) -> Result<Reply, SendError> {
+    if is_offline() {
+        let failure = RequestFailure::Offline;
+        audit::log_request("GET", url, authenticate, Err(failure), api);
+        return Err(SendError::Offline);
+    }
+    thread::sleep(throttle::reserve(url, false));
+    let reply = try_get(url, authenticate, api)?;
+    if reply.status.is_success() {
+        throttle::record_success(url);
+    }
+    Ok(reply)
+}
+
+/// Send a POST request to `url` on behalf of the library function `api`.
+///
+/// POST requests are never retried if they may have reached Advent of Code, as
+/// they may not be safe to repeat. Nothing is sent in offline mode.
 pub(crate) fn post(
     url: &str,
     authenticate: bool,
     data: impl serde::Serialize,
-) -> reqwest::blocking::Response {
-    if authenticate {
-        CLIENT.post(url).header(header::COOKIE, get_cookie())
-    } else {
-        CLIENT.post(url)
+    api: &str,
+) -> Result<Reply, SendError> {
+    if is_offline() {
+        let failure = RequestFailure::Offline;
+        audit::log_request("POST", url, authenticate, Err(failure), api);
+        return Err(SendError::Offline);
     }
-    .form(&data)
-    .send()
-    .expect("Advent of Code sent back a bad response, or the network is down.")
+    // answers are deduplicated by the submission cache, and are only resent
+    // when Advent of Code asks us to wait and try again
+    thread::sleep(throttle::reserve(url, true));
+    let cookie = authenticate.then(get_cookie);
+    send("POST", url, authenticate, api, false, || {
+        let request = CLIENT.post(url).form(&data);
+        match &cookie {
+            Some(cookie) => request.header(header::COOKIE, cookie),
+            None => request,
+        }
+    })
 }
 
-pub(crate) fn load_leaderboard_times(day: u32, year: i32) -> (Vec<f64>, Vec<f64>) {
-    let day_dir = &*DATA_DIR / year.to_string() / day.to_string();
-    make(&day_dir);
+/// Check the puzzle page to see whether `answer` was accepted for `part`,
+/// after submitting it failed in a way that it may have been received.
+///
+/// Returns [`None`] if the puzzle page can't be fetched either.
+pub(crate) fn check_answer_accepted(
+    day: u32,
+    part: u32,
+    year: impl Display,
+    answer: &str,
+) -> Option<bool> {
+    let url = base_url(year, day);
+    eprintln!(
+        "{}",
+        "Your answer may not have reached Advent of Code; checking the puzzle page..."
+            .yellow()
+    );
+    // the page has (probably) changed since it was last fetched, so it's fine
+    // to fetch it again immediately
+    thread::sleep(throttle::reserve(&url, true));
+    try_get(&url, true, "submit")
+        .ok()
+        .filter(|reply| reply.status.is_success())
+        .map(|reply| answer_accepted(&reply.body, day, part, answer))
+}
+
+pub(crate) fn load_leaderboard_times(
+    day: u32,
+    year: i32,
+) -> Result<(Vec<f64>, Vec<f64>), SendError> {
+    make(&day_dir(year, day));
 
-    let leaderboards = day_dir / "leaderboards.json";
+    let leaderboards = leaderboards_file(year, day);
     if leaderboards.exists() {
         let data = fs::read_to_string(&leaderboards)
             .expect("Failed to read leaderboards file.");
-        serde_json::from_str(&data).expect("Failed to parse leaderboard cache.")
+        Ok(serde_json::from_str(&data).expect("Failed to parse leaderboard cache."))
     } else {
-        let leaderboard_page = get_text(&leaderboard_url(year, day), false);
+        let leaderboard_page =
+            get(&leaderboard_url(year, day), false, "leaderboard")?.body;
         let soup = tl::parse(&leaderboard_page, tl::ParserOptions::new())
             .expect("Parsing the leaderboard page failed.");
         let times = soup
@@ -224,20 +423,19 @@ pub(crate) fn load_leaderboard_times(day: u32, year: i32) -> (Vec<f64>, Vec<f64>
         }
         if part_1_times.len() == 100 && part_2_times.len() == 100 {
             // Both leaderboards are full, cache them
-            serde_json::to_writer(
-                fs::File::create(&leaderboards).expect(
-                    "Failed to create leaderboard cache. Please check your permissions",
-                ),
-                &(&part_1_times, &part_2_times),
+            write_atomic(
+                &leaderboards,
+                serde_json::to_string(&(&part_1_times, &part_2_times))
+                    .expect("Serialising should never fail"),
             )
             .expect("Failed to write leaderboard cache. Please check your permissions");
         }
-        (part_1_times, part_2_times)
+        Ok((part_1_times, part_2_times))
     }
 }
 
 pub(crate) fn practice_result_for(day: u32, year: i32) -> (PathDiv, Vec<f64>) {
-    let practice_data_dir = &*PRACTICE_DATA_DIR / year.to_string() / day.to_string();
+    let practice_data_dir = practice_dir(year, day);
     make(&practice_data_dir);
     let now = Utc::now();
     let file = practice_data_dir
@@ -282,28 +480,26 @@ pub(crate) fn calculate_practice_result(day: u32, part: u32, year: i32) {
         .to_std()
         .expect("Should never be negative")
         .as_secs_f64();
-    let (file, mut data) = practice_result_for(day, year);
-    data.push(solve_time);
-    serde_json::to_writer(
-        fs::File::create(file).expect("Opening practice result file failed"),
-        &data,
-    )
-    .expect("Saving practice results failed");
+    make(&practice_dir(year, day));
+    {
+        // another process may record a result for this day while we do
+        let _lock = CacheLock::acquire(&practice_dir(year, day));
+        let (file, mut data) = practice_result_for(day, year);
+        data.push(solve_time);
+        write_atomic(
+            &file,
+            serde_json::to_string(&data)
+                .expect("Serialising results should be infallible"),
+        )
+        .expect("Saving practice results failed");
+    }
     report_practice_result(day, part, year, solve_time);
 }
 
 fn estimate_practice_rank(
-    day: u32,
-    part: u32,
-    year: i32,
+    leaderboard: &[f64],
     solve_time: f64,
 ) -> Option<(usize, usize, usize)> {
-    let leaderboard = load_leaderboard_times(day, year);
-    let leaderboard = match part {
-        1 => leaderboard.0,
-        2 => leaderboard.1,
-        _ => panic!("part was neither 1 nor 2"),
-    };
     let truncated_solve_time = solve_time.trunc();
     let best_possible_rank =
         leaderboard.partition_point(|&opp_time| opp_time < truncated_solve_time) + 1;
@@ -323,6 +519,20 @@ fn estimate_practice_rank(
     }
 }
 
+/// Estimate the rank a solve off the global leaderboard would have achieved,
+/// and the percentage of all solvers that rank places it in.
+fn estimate_practice_percentile(
+    leaderboard: &[f64],
+    day: u32,
+    part: u32,
+    year: i32,
+    solve_time: f64,
+) -> Option<(f64, f64)> {
+    let rank = extrapolate_rank(leaderboard, solve_time)?;
+    let percentile = try_global_stats(year).ok()?.percentile(day, part, rank)?;
+    Some((rank, percentile))
+}
+
 fn report_practice_result(day: u32, part: u32, year: i32, solve_time: f64) {
     println!(
         "{} {}{}",
@@ -330,14 +540,52 @@ fn report_practice_result(day: u32, part: u32, year: i32, solve_time: f64) {
         format_time(solve_time).blue(),
         '!'.green(),
     );
+    if is_offline() {
+        println!(
+            "{}",
+            "Skipping leaderboard estimation, as raoc is offline.".yellow()
+        );
+        return;
+    }
 
-    let result = estimate_practice_rank(day, part, year, solve_time);
-    match result {
-        None => {
+    let leaderboard = match load_leaderboard_times(day, year) {
+        Ok(leaderboard) => leaderboard,
+        Err(err) => {
             println!(
                 "{}",
-                "You would not have achieved a leaderboard position.".yellow()
+                format!("Skipping leaderboard estimation, as {err}.").yellow()
             );
+            return;
+        },
+    };
+    let leaderboard = match part {
+        1 => leaderboard.0,
+        2 => leaderboard.1,
+        _ => panic!("part was neither 1 nor 2"),
+    };
+    let result = estimate_practice_rank(&leaderboard, solve_time);
+    match result {
+        None => match estimate_practice_percentile(
+            &leaderboard,
+            day,
+            part,
+            year,
+            solve_time,
+        ) {
+            Some((rank, percentile)) => println!(
+                "{} {} {}{}{}",
+                "You would not have achieved a leaderboard position, but would have \
+                 achieved approximately rank"
+                    .yellow(),
+                format!("{rank:.0}").blue(),
+                "(top ".yellow(),
+                format!("{percentile:.2}%").blue(),
+                " of solvers).".yellow(),
+            ),
+            None => println!(
+                "{}",
+                "You would not have achieved a leaderboard position.".yellow()
+            ),
         },
         Some((_approx, best, worst)) if best == worst => {
             println!(
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{write_atomic, CacheLock};
+use crate::data::{DATA_DIR, MIN_REQUEST_SPACING, REPEAT_WINDOW};
+use crate::internal_util::duration_setting;
+
+/// Serialises requests between threads; the lock on [`STATE_FILE`] does the
+/// same between processes.
+static THROTTLE: Mutex<()> = Mutex::new(());
+
+/// Every request made through `raoc`, shared between processes.
+const STATE_FILE: &str = "throttle.json";
+/// The minimum number of seconds between any two requests.
+const SPACING_FILE: &str = "request_spacing.txt";
+/// The number of seconds during which a successful request may not be
+/// repeated.
+const WINDOW_FILE: &str = "repeat_window.txt";
+
+#[derive(Default, Serialize, Deserialize)]
+struct ThrottleState {
+    /// When the most recent request was (or will be) sent, in seconds since
+    /// the Unix epoch.
+    next_request: f64,
+    /// When each URL was last fetched successfully, in seconds since the Unix
+    /// epoch.
+    recent: HashMap<String, f64>,
+}
+
+impl ThrottleState {
+    /// Reserve the next free slot at or after `now` for a request to `url`,
+    /// returning when it is, or how long ago `url` was fetched if the request
+    /// must be refused.
+    fn reserve(
+        &mut self,
+        url: &str,
+        repeatable: bool,
+        now: f64,
+        spacing: f64,
+        window: f64,
+    ) -> Result<f64, f64> {
+        self.recent.retain(|_, &mut fetched| now - fetched < window);
+        if !repeatable {
+            if let Some(fetched) = self.recent.get(url) {
+                return Err(now - fetched);
+            }
+        }
+        let slot = now.max(self.next_request + spacing);
+        self.next_request = slot;
+        Ok(slot)
+    }
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |time| time.as_secs_f64())
+}
+
+/// Run `update` on the shared throttle state, with every other thread and
+/// process locked out.
+fn with_state<T>(update: impl FnOnce(&mut ThrottleState) -> T) -> T {
+    let _guard = THROTTLE.lock().unwrap_or_else(PoisonError::into_inner);
+    let file = &*DATA_DIR / STATE_FILE;
+    let _lock = CacheLock::acquire(&file);
+    let mut state = fs::read_to_string(&file)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default();
+    let result = update(&mut state);
+    let _ = write_atomic(
+        &file,
+        serde_json::to_string(&state).expect("Serialising should never fail"),
+    );
+    result
+}
+
+/// Reserve a slot to send a request to `url`, returning how long to wait
+/// before sending it.
+///
+/// Requests are spaced at least a second apart (configurable in
+/// `request_spacing.txt` in the data directory), across every process using
+/// `raoc`. Unless `repeatable` is set, a URL which was fetched successfully in
+/// the last 30 seconds (configurable in `repeat_window.txt`) is refused
+/// outright, as there's no reason to ask for the same thing again so soon.
+///
+/// # Panics
+///
+/// If the request is refused.
+pub(crate) fn reserve(url: &str, repeatable: bool) -> Duration {
+    let spacing = duration_setting(SPACING_FILE, MIN_REQUEST_SPACING).as_secs_f64();
+    let window = duration_setting(WINDOW_FILE, REPEAT_WINDOW).as_secs_f64();
+    let now = now();
+    let slot = with_state(|state| state.reserve(url, repeatable, now, spacing, window))
+        .unwrap_or_else(|age| {
+            panic!(
+                "Refusing to request {url} again only {age:.0} seconds after it was \
+                 last fetched. Please cache the response instead; see \
+                 https://www.reddit.com/r/adventofcode/wiki/faqs/automation"
+            )
+        });
+    Duration::from_secs_f64(slot - now)
+}
+
+/// Record that `url` was fetched successfully, so that it won't be fetched
+/// again too soon.
+pub(crate) fn record_success(url: &str) {
+    with_state(|state| {
+        state.recent.insert(url.to_string(), now());
+    });
+}
+
+/// Forget which URLs were fetched successfully, once the session token has
+/// been replaced: they were fetched with the old token, so may have been
+/// nothing but login pages.
+pub(crate) fn forget_successes() {
+    with_state(|state| state.recent.clear());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ThrottleState;
+
+    const URL: &str = "https://adventofcode.com/2023/day/1/input";
+
+    #[test]
+    fn requests_are_spaced_out() {
+        let mut state = ThrottleState::default();
+        assert_eq!(state.reserve(URL, false, 100.0, 1.0, 30.0), Ok(100.0));
+        // queued behind the first, even for a different URL
+        assert_eq!(
+            state.reserve("/2023/day/2", false, 100.0, 1.0, 30.0),
+            Ok(101.0)
+        );
+        assert_eq!(
+            state.reserve("/2023/day/3", false, 100.5, 1.0, 30.0),
+            Ok(102.0)
+        );
+        // no waiting once the queue has cleared
+        assert_eq!(
+            state.reserve("/2023/day/4", false, 110.0, 1.0, 30.0),
+            Ok(110.0)
+        );
+    }
+
+    #[test]
+    fn successes_are_not_repeated() {
+        let mut state = ThrottleState::default();
+        state.recent.insert(URL.into(), 100.0);
+        assert_eq!(state.reserve(URL, false, 110.0, 1.0, 30.0), Err(10.0));
+        // a refused request doesn't take a slot
+        assert_eq!(state.next_request, 0.0);
+        assert_eq!(state.reserve(URL, true, 110.0, 1.0, 30.0), Ok(110.0));
+    }
+
+    #[test]
+    fn successes_expire() {
+        let mut state = ThrottleState::default();
+        state.recent.insert(URL.into(), 100.0);
+        assert_eq!(state.reserve(URL, false, 130.0, 1.0, 30.0), Ok(130.0));
+        assert!(state.recent.is_empty());
+    }
+}
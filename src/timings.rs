@@ -0,0 +1,476 @@
+use std::fmt::{self, Display};
+use std::fs;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use crossterm::style::Stylize;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::alloc::{MemoryProbe, MemoryUsage};
+use crate::cache::{numbered_dirs, timings_file, write_atomic, year_dir, CacheLock};
+use crate::data::{DATA_DIR, REGRESSION_THRESHOLD};
+
+/// The percentage by which a run must be slower than the best recorded run to
+/// be reported as a regression.
+const THRESHOLD_FILE: &str = "regression_threshold.txt";
+/// The number of recent runs shown in a timing report.
+const RECENT_RUNS: usize = 5;
+
+lazy_static! {
+    /// The commit checked out in the working directory, if it's a git
+    /// repository.
+    static ref COMMIT: Option<String> = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+        .filter(|commit| !commit.is_empty());
+}
+
+/// The build profile `raoc` (and so, almost always, the solution) was compiled
+/// with.
+fn build_profile() -> String {
+    if cfg!(debug_assertions) {
+        "debug".into()
+    } else {
+        "release".into()
+    }
+}
+
+fn unknown_profile() -> String {
+    "unknown".into()
+}
+
+/// How long each stage of one run of a solution took.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunTimings {
+    /// When the solution was run, in seconds since the Unix epoch.
+    pub timestamp: i64,
+    /// The part the solution was for.
+    pub part: u32,
+    /// The git commit checked out when the solution was run, if any.
+    #[serde(default)]
+    pub commit: Option<String>,
+    /// The build profile the solution was compiled with: `debug` or
+    /// `release` (or `unknown`, for runs recorded before profiles were).
+    #[serde(default = "unknown_profile")]
+    pub profile: String,
+    /// How long fetching (or loading the cached) input took, in seconds.
+    pub fetch: f64,
+    /// How long parsing the input took, in seconds.
+    pub parse: f64,
+    /// How long the solution took, in seconds.
+    pub solve: f64,
+    /// The memory used while parsing the input, if
+    /// [`CountingAllocator`](crate::CountingAllocator) was installed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse_memory: Option<MemoryUsage>,
+    /// The memory used by the solution, if
+    /// [`CountingAllocator`](crate::CountingAllocator) was installed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solve_memory: Option<MemoryUsage>,
+}
+
+impl RunTimings {
+    /// When the solution was run.
+    #[must_use]
+    pub fn time(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.timestamp, 0).unwrap_or_default()
+    }
+
+    /// How long parsing and solving took together, in seconds.
+    #[must_use]
+    pub fn runtime(&self) -> f64 {
+        self.parse + self.solve
+    }
+}
+
+/// Every run recorded for `day` of `year`, oldest first.
+#[must_use]
+pub fn run_timings(year: i32, day: u32) -> Vec<RunTimings> {
+    fs::read_to_string(timings_file(year, day))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// The time and memory taken by one stage of a run.
+#[derive(Clone, Copy)]
+pub(crate) struct Stage {
+    pub(crate) time: Duration,
+    pub(crate) memory: Option<MemoryUsage>,
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format!("{:.2?}", self.time).blue())?;
+        if let Some(memory) = self.memory {
+            write!(f, " {}{memory}{}", '('.yellow(), ')'.yellow())?;
+        }
+        Ok(())
+    }
+}
+
+/// Measures the time (and, with [`CountingAllocator`](crate::CountingAllocator)
+/// installed, the memory) taken by a stage of a run.
+pub(crate) struct Stopwatch {
+    start: Instant,
+    memory: Option<MemoryProbe>,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        let memory = MemoryProbe::start();
+        Self {
+            start: Instant::now(),
+            memory,
+        }
+    }
+
+    pub(crate) fn stop(self) -> Stage {
+        let time = self.start.elapsed();
+        Stage {
+            time,
+            memory: self.memory.map(MemoryProbe::finish),
+        }
+    }
+}
+
+/// Report how long (and how much memory) each stage of a run took.
+pub(crate) fn print_run_timings(fetch: Duration, parse: &Stage, solve: &Stage) {
+    println!(
+        "{} {}{} {parse}{} {solve}",
+        "Fetched in".yellow(),
+        format!("{fetch:.2?}").blue(),
+        ", parsed in".yellow(),
+        ", solved in".yellow(),
+    );
+}
+
+/// Record a run in the day's cache.
+pub(crate) fn record_run(
+    year: i32,
+    day: u32,
+    part: u32,
+    fetch: Duration,
+    parse: &Stage,
+    solve: &Stage,
+) {
+    let file = timings_file(year, day);
+    let _lock = CacheLock::acquire(&file);
+    let mut runs = run_timings(year, day);
+    runs.push(RunTimings {
+        timestamp: Utc::now().timestamp(),
+        part,
+        commit: COMMIT.clone(),
+        profile: build_profile(),
+        fetch: fetch.as_secs_f64(),
+        parse: parse.time.as_secs_f64(),
+        solve: solve.time.as_secs_f64(),
+        parse_memory: parse.memory,
+        solve_memory: solve.memory,
+    });
+    write_atomic(
+        &file,
+        serde_json::to_string(&runs).expect("Serialising should never fail"),
+    )
+    .unwrap_or_else(|_| {
+        eprintln!(
+            "{}",
+            "Warning: Failed to record timings. Please check your permissions.".red()
+        );
+    });
+}
+
+/// The configured regression threshold, as a percentage: 10% unless
+/// `regression_threshold.txt` in the data directory says otherwise.
+#[must_use]
+pub fn regression_threshold() -> f64 {
+    fs::read_to_string(&*DATA_DIR / THRESHOLD_FILE)
+        .ok()
+        .and_then(|threshold| threshold.trim().trim_end_matches('%').parse().ok())
+        .filter(|threshold: &f64| threshold.is_finite() && *threshold >= 0.0)
+        .unwrap_or(REGRESSION_THRESHOLD)
+}
+
+/// How the runtime of one part's solution has changed over time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartTrend {
+    /// The year of the puzzle.
+    pub year: i32,
+    /// The day of the puzzle.
+    pub day: u32,
+    /// The part of the puzzle.
+    pub part: u32,
+    /// The number of runs recorded with the same build profile as the latest.
+    pub runs: usize,
+    /// The fastest run with the same build profile as the latest.
+    pub best: RunTimings,
+    /// The most recent run.
+    pub latest: RunTimings,
+    /// The runtimes of the most recent runs (up to 5), oldest first, in
+    /// seconds.
+    pub recent: Vec<f64>,
+    /// Whether the latest run is slower than the best by more than the
+    /// threshold.
+    pub regression: bool,
+}
+
+impl PartTrend {
+    fn new(
+        year: i32,
+        day: u32,
+        part: u32,
+        runs: &[RunTimings],
+        threshold: f64,
+    ) -> Option<Self> {
+        let latest = runs.iter().rev().find(|run| run.part == part)?;
+        // debug and release runtimes aren't comparable
+        let runs = runs
+            .iter()
+            .filter(|run| run.part == part && run.profile == latest.profile)
+            .collect::<Vec<_>>();
+        let best = runs
+            .iter()
+            .copied()
+            .min_by(|a, b| a.runtime().total_cmp(&b.runtime()))
+            .expect("The latest run is always included");
+        Some(Self {
+            year,
+            day,
+            part,
+            runs: runs.len(),
+            best: best.clone(),
+            latest: latest.clone(),
+            recent: runs[runs.len().saturating_sub(RECENT_RUNS)..]
+                .iter()
+                .map(|run| run.runtime())
+                .collect(),
+            regression: latest.runtime() > best.runtime() * (1.0 + threshold / 100.0),
+        })
+    }
+
+    /// How much slower (or, if negative, faster) the latest run was than the
+    /// best, as a percentage.
+    #[must_use]
+    pub fn change(&self) -> f64 {
+        if self.best.runtime() == 0.0 {
+            0.0
+        } else {
+            (self.latest.runtime() / self.best.runtime() - 1.0) * 100.0
+        }
+    }
+}
+
+/// The runtime trend of every part with recorded runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimingReport {
+    /// The percentage by which the latest run must be slower than the best to
+    /// count as a regression.
+    pub threshold: f64,
+    /// The trend for each part, ordered by year, day and part.
+    pub parts: Vec<PartTrend>,
+}
+
+impl TimingReport {
+    /// The parts whose latest run is a regression.
+    pub fn regressions(&self) -> impl Iterator<Item = &PartTrend> {
+        self.parts.iter().filter(|trend| trend.regression)
+    }
+}
+
+/// Summarise the recorded runs for every puzzle (optionally only for `year`),
+/// flagging parts whose latest run is more than `threshold` percent slower
+/// than their best.
+#[must_use]
+pub fn timing_report(year: Option<i32>, threshold: f64) -> TimingReport {
+    let years = match year {
+        Some(year) => vec![year],
+        None => numbered_dirs(&DATA_DIR)
+            .into_iter()
+            .filter_map(|(year, _)| i32::try_from(year).ok())
+            .filter(|&year| year >= 2015)
+            .collect(),
+    };
+    let mut parts = Vec::new();
+    for year in years {
+        for (day, _) in numbered_dirs(&year_dir(year)) {
+            let Ok(day) = u32::try_from(day) else {
+                continue;
+            };
+            let runs = run_timings(year, day);
+            parts.extend(
+                (1..=2).filter_map(|part| {
+                    PartTrend::new(year, day, part, &runs, threshold)
+                }),
+            );
+        }
+    }
+    TimingReport { threshold, parts }
+}
+
+fn format_runtime(seconds: f64) -> String {
+    format!("{:.2?}", Duration::from_secs_f64(seconds))
+}
+
+impl Display for PartTrend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let change = format!("{:>8}", format!("{:+.1}%", self.change()));
+        let change = if self.regression {
+            change.red()
+        } else if self.change() <= 0.0 {
+            change.green()
+        } else {
+            change.yellow()
+        };
+        write!(
+            f,
+            "{:>4} {:>3} {:>4} {:<7} {:>4}  {:>10} {:<9} {:>10} {:<9} {change}  {}",
+            self.year,
+            self.day,
+            self.part,
+            self.latest.profile,
+            self.runs,
+            format_runtime(self.best.runtime()),
+            self.best.commit.as_deref().unwrap_or("-"),
+            format_runtime(self.latest.runtime()),
+            self.latest.commit.as_deref().unwrap_or("-"),
+            self.recent
+                .iter()
+                .map(|&runtime| format_runtime(runtime))
+                .collect::<Vec<_>>()
+                .join(" -> "),
+        )?;
+        if self.regression {
+            write!(f, " {}", "REGRESSION".red())?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TimingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}",
+            format!(
+                "{:>4} {:>3} {:>4} {:<7} {:>4}  {:>10} {:<9} {:>10} {:<9} {:>8}  {}",
+                "Year",
+                "Day",
+                "Part",
+                "Profile",
+                "Runs",
+                "Best",
+                "",
+                "Latest",
+                "",
+                "Change",
+                "Recent"
+            )
+            .yellow()
+        )?;
+        for trend in &self.parts {
+            writeln!(f, "{trend}")?;
+        }
+        let regressions = self.regressions().count();
+        if regressions > 0 {
+            writeln!(
+                f,
+                "{}",
+                format!(
+                    "{regressions} {} more than {}% slower than the best recorded run.",
+                    if regressions == 1 {
+                        "part is"
+                    } else {
+                        "parts are"
+                    },
+                    self.threshold,
+                )
+                .red()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PartTrend, RunTimings};
+
+    fn run(part: u32, profile: &str, parse: f64, solve: f64) -> RunTimings {
+        RunTimings {
+            timestamp: 0,
+            part,
+            commit: None,
+            profile: profile.into(),
+            fetch: 0.0,
+            parse,
+            solve,
+            parse_memory: None,
+            solve_memory: None,
+        }
+    }
+
+    #[test]
+    fn trend_compares_the_latest_run_with_the_best() {
+        let runs = [
+            run(1, "release", 0.5, 2.5),
+            run(1, "release", 0.5, 1.5),
+            run(2, "release", 1.0, 9.0),
+            run(1, "release", 0.5, 2.0),
+        ];
+        let trend = PartTrend::new(2023, 1, 1, &runs, 10.0).expect("Part 1 was run");
+        assert_eq!(trend.runs, 3);
+        assert_eq!(trend.best.runtime(), 2.0);
+        assert_eq!(trend.latest.runtime(), 2.5);
+        assert_eq!(trend.recent, [3.0, 2.0, 2.5]);
+        assert_eq!(trend.change(), 25.0);
+        assert!(trend.regression);
+    }
+
+    #[test]
+    fn regression_must_exceed_the_threshold() {
+        let runs = [run(1, "release", 0.0, 2.0), run(1, "release", 0.0, 2.5)];
+        let at = PartTrend::new(2023, 1, 1, &runs, 25.0).expect("Part 1 was run");
+        assert!(!at.regression);
+        let over = PartTrend::new(2023, 1, 1, &runs, 24.9).expect("Part 1 was run");
+        assert!(over.regression);
+    }
+
+    #[test]
+    fn trend_only_compares_the_latest_profile() {
+        let runs = [run(1, "release", 0.0, 1.0), run(1, "debug", 0.0, 30.0)];
+        let trend = PartTrend::new(2023, 1, 1, &runs, 10.0).expect("Part 1 was run");
+        assert_eq!(trend.runs, 1);
+        assert_eq!(trend.best.profile, "debug");
+        assert!(!trend.regression);
+    }
+
+    #[test]
+    fn trend_shows_the_most_recent_runs() {
+        let runs = (1..=8)
+            .map(|solve| run(2, "release", 0.0, f64::from(solve)))
+            .collect::<Vec<_>>();
+        let trend = PartTrend::new(2023, 1, 2, &runs, 10.0).expect("Part 2 was run");
+        assert_eq!(trend.recent, [4.0, 5.0, 6.0, 7.0, 8.0]);
+    }
+
+    #[test]
+    fn trend_needs_a_run() {
+        let runs = [run(1, "release", 0.0, 1.0)];
+        assert!(PartTrend::new(2023, 1, 2, &runs, 10.0).is_none());
+        assert!(PartTrend::new(2023, 1, 1, &[], 10.0).is_none());
+    }
+
+    #[test]
+    fn instant_runs_have_no_change() {
+        let runs = [run(1, "release", 0.0, 0.0), run(1, "release", 0.0, 0.0)];
+        let trend = PartTrend::new(2023, 1, 1, &runs, 10.0).expect("Part 1 was run");
+        assert_eq!(trend.change(), 0.0);
+        assert!(!trend.regression);
+    }
+}